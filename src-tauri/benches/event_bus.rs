@@ -0,0 +1,240 @@
+// Fan-out benchmark: 50 clients, watch<Vec<String>> + per-client serialization (old /ws loop)
+// vs. the pre-serialized broadcast bus.
+//
+//   cargo bench --manifest-path src-tauri/Cargo.toml --bench event_bus
+//
+// Without `--bench` (e.g. `cargo test --all-targets`) it runs a short smoke pass.
+
+// bus.rs only needs the stream tag from the protocol module
+mod protocol {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Stream {
+        Keys,
+    }
+}
+
+#[path = "../src/bus.rs"]
+#[allow(dead_code)]
+mod bus;
+
+use bus::EventBus;
+use parking_lot::RwLock;
+use protocol::Stream;
+use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, watch};
+
+const CLIENTS: usize = 50;
+// Gap between published events; a fast typist is ~15-20 transitions per second,
+// so this is far above anything a real overlay sees.
+const EVENT_INTERVAL: Duration = Duration::from_micros(500);
+
+struct Report {
+    name: &'static str,
+    events: usize,
+    delivered: usize,
+    serializations: u64,
+    latencies_us: Vec<f64>,
+    cpu_ms: Option<f64>,
+    wall_ms: f64,
+}
+
+impl Report {
+    fn print(&mut self) {
+        self.latencies_us.sort_by(|a, b| a.total_cmp(b));
+        let pct = |p: f64| {
+            if self.latencies_us.is_empty() {
+                return 0.0;
+            }
+            let idx = ((self.latencies_us.len() - 1) as f64 * p).round() as usize;
+            self.latencies_us[idx]
+        };
+        println!(
+            "{:<8} delivered {:>6}/{:<6} p50 {:>8.1}us  p99 {:>8.1}us  max {:>8.1}us  serializations/event {:>5.1}  cpu {}  wall {:.0}ms",
+            self.name,
+            self.delivered,
+            self.events * CLIENTS,
+            pct(0.50),
+            pct(0.99),
+            pct(1.0),
+            self.serializations as f64 / self.events as f64,
+            self.cpu_ms
+                .map(|ms| format!("{:>7.1}ms", ms))
+                .unwrap_or_else(|| "    n/a".to_string()),
+            self.wall_ms,
+        );
+    }
+}
+
+// Process CPU time (user + system); Linux only, assumes the usual 100 ticks/s
+fn cpu_time_ms() -> Option<f64> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // Fields after the parenthesised command name; utime/stime are the 14th/15th overall
+    let rest = stat.rsplit_once(')')?.1;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let utime: f64 = fields.get(11)?.parse().ok()?;
+    let stime: f64 = fields.get(12)?.parse().ok()?;
+    Some((utime + stime) * 10.0)
+}
+
+// Held labels after event `i`: alternate press/release of a few keys
+fn keys_at(i: usize) -> Vec<String> {
+    let mut keys = vec!["SHIFT".to_string()];
+    if i & 1 == 0 {
+        keys.push(format!("KEY{}", i % 26));
+    }
+    keys
+}
+
+fn elapsed_us(start: Instant, published_ns: &AtomicU64) -> f64 {
+    let now = start.elapsed().as_nanos() as u64;
+    now.saturating_sub(published_ns.load(Ordering::Acquire)) as f64 / 1000.0
+}
+
+// Old model: every client wakes on watch, re-reads shared state, serializes and dedups itself
+async fn run_watch(events: usize) -> Report {
+    let state = Arc::new(RwLock::new((0usize, Vec::<String>::new())));
+    let (tx, _rx) = watch::channel(());
+    let published: Arc<Vec<AtomicU64>> = Arc::new((0..events).map(|_| AtomicU64::new(0)).collect());
+    let serializations = Arc::new(AtomicU64::new(0));
+    let start = Instant::now();
+
+    let mut clients = Vec::new();
+    for _ in 0..CLIENTS {
+        let mut rx = tx.subscribe();
+        let state = state.clone();
+        let published = published.clone();
+        let serializations = serializations.clone();
+        clients.push(tokio::spawn(async move {
+            let mut latencies = Vec::with_capacity(events);
+            let mut last_sent = String::new();
+            while rx.changed().await.is_ok() {
+                let (idx, keys) = state.read().clone();
+                let msg = json!({ "type": "keys", "keys": keys }).to_string();
+                serializations.fetch_add(1, Ordering::Relaxed);
+                if msg != last_sent {
+                    // Stand-in for Message::Text(msg)
+                    last_sent = msg;
+                    latencies.push(elapsed_us(start, &published[idx]));
+                }
+                if idx + 1 == events {
+                    break;
+                }
+            }
+            latencies
+        }));
+    }
+
+    let cpu_before = cpu_time_ms();
+    let wall = Instant::now();
+    for i in 0..events {
+        tokio::time::sleep(EVENT_INTERVAL).await;
+        *state.write() = (i, keys_at(i));
+        published[i].store(start.elapsed().as_nanos() as u64, Ordering::Release);
+        let _ = tx.send(());
+    }
+    let mut latencies_us = Vec::new();
+    for client in clients {
+        latencies_us.extend(client.await.unwrap_or_default());
+    }
+    Report {
+        name: "watch",
+        events,
+        delivered: latencies_us.len(),
+        serializations: serializations.load(Ordering::Relaxed),
+        latencies_us,
+        cpu_ms: cpu_time_ms()
+            .zip(cpu_before)
+            .map(|(after, before)| after - before),
+        wall_ms: wall.elapsed().as_secs_f64() * 1000.0,
+    }
+}
+
+// New model: serialize once, share the frame, clients only filter and copy
+async fn run_bus(events: usize) -> Report {
+    let bus = Arc::new(RwLock::new(EventBus::new(1024, 1024)));
+    let published: Arc<Vec<AtomicU64>> = Arc::new((0..events).map(|_| AtomicU64::new(0)).collect());
+    let mut serializations = 0u64;
+    let start = Instant::now();
+
+    let mut clients = Vec::new();
+    for _ in 0..CLIENTS {
+        let mut rx = bus.read().subscribe();
+        let published = published.clone();
+        clients.push(tokio::spawn(async move {
+            let mut latencies = Vec::with_capacity(events);
+            let mut lagged = 0u64;
+            loop {
+                match rx.recv().await {
+                    Ok(frame) => {
+                        // Stand-in for Message::Text(frame.json.to_string())
+                        let payload = frame.json.to_string();
+                        std::hint::black_box(payload);
+                        let idx = frame.seq as usize - 1;
+                        latencies.push(elapsed_us(start, &published[idx]));
+                        if idx + 1 == events {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => lagged += n,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            (latencies, lagged)
+        }));
+    }
+
+    let cpu_before = cpu_time_ms();
+    let wall = Instant::now();
+    for i in 0..events {
+        tokio::time::sleep(EVENT_INTERVAL).await;
+        let msg = json!({ "type": "keys", "keys": keys_at(i) }).to_string();
+        serializations += 1;
+        let mut bus = bus.write();
+        published[i].store(start.elapsed().as_nanos() as u64, Ordering::Release);
+        bus.publish(Some(Stream::Keys), msg);
+    }
+    let mut latencies_us = Vec::new();
+    let mut lagged = 0;
+    for client in clients {
+        let (latencies, client_lagged) = client.await.unwrap_or_default();
+        latencies_us.extend(latencies);
+        lagged += client_lagged;
+    }
+    if lagged > 0 {
+        println!("bus: {} frames skipped by lagging clients", lagged);
+    }
+    Report {
+        name: "bus",
+        events,
+        delivered: latencies_us.len(),
+        serializations,
+        latencies_us,
+        cpu_ms: cpu_time_ms()
+            .zip(cpu_before)
+            .map(|(after, before)| after - before),
+        wall_ms: wall.elapsed().as_secs_f64() * 1000.0,
+    }
+}
+
+fn main() {
+    let full = std::env::args().any(|arg| arg == "--bench");
+    let events = if full { 5000 } else { 200 };
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .enable_all()
+        .build()
+        .expect("tokio runtime");
+
+    println!(
+        "{} clients, {} events, {:?} apart",
+        CLIENTS, events, EVENT_INTERVAL
+    );
+    runtime.block_on(async {
+        run_watch(events).await.print();
+        run_bus(events).await.print();
+    });
+}
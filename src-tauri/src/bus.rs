@@ -0,0 +1,117 @@
+use crate::protocol::Stream;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// One outgoing message, serialized once and shared by every connected client
+#[derive(Debug)]
+pub struct Frame {
+    /// Position in the retained sequence. Transient frames repeat the latest retained seq.
+    pub seq: u64,
+    /// Stream used for per-client filtering; `None` is delivered to everyone
+    pub stream: Option<Stream>,
+    /// Not kept in the history and never replayed (periodic stats, foreground polls)
+    pub transient: bool,
+    /// Nothing follows this frame; clients should close after delivering it
+    pub last: bool,
+    pub json: Arc<str>,
+}
+
+/// Fan-out of pre-serialized frames to all WS/SSE clients.
+/// Keeps the most recent retained frames so lagging or reconnecting clients can catch up.
+pub struct EventBus {
+    tx: broadcast::Sender<Arc<Frame>>,
+    seq: u64,
+    history: VecDeque<Arc<Frame>>,
+    history_len: usize,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize, history_len: usize) -> Self {
+        let (tx, _rx) = broadcast::channel(capacity);
+        Self {
+            tx,
+            seq: 0,
+            history: VecDeque::with_capacity(history_len),
+            history_len,
+        }
+    }
+
+    // Sequence number of the last retained frame (0 = nothing published yet)
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Frame>> {
+        self.tx.subscribe()
+    }
+
+    pub fn receiver_count(&self) -> usize {
+        self.tx.receiver_count()
+    }
+
+    pub fn publish(&mut self, stream: Option<Stream>, json: String) -> Arc<Frame> {
+        self.seq += 1;
+        let frame = Arc::new(Frame {
+            seq: self.seq,
+            stream,
+            transient: false,
+            last: false,
+            json: json.into(),
+        });
+        if self.history.len() >= self.history_len {
+            self.history.pop_front();
+        }
+        self.history.push_back(frame.clone());
+        self.send(frame)
+    }
+
+    pub fn publish_transient(&mut self, stream: Option<Stream>, json: String) -> Arc<Frame> {
+        self.send(Arc::new(Frame {
+            seq: self.seq,
+            stream,
+            transient: true,
+            last: false,
+            json: json.into(),
+        }))
+    }
+
+    // Final frame of a session (server shutdown)
+    pub fn publish_last(&mut self, json: String) -> Arc<Frame> {
+        self.send(Arc::new(Frame {
+            seq: self.seq,
+            stream: None,
+            transient: true,
+            last: true,
+            json: json.into(),
+        }))
+    }
+
+    fn send(&self, frame: Arc<Frame>) -> Arc<Frame> {
+        // Err only means nobody is listening right now
+        let _ = self.tx.send(frame.clone());
+        frame
+    }
+
+    // Retained frames after `seq`, or None if some of them already fell out of the history
+    // (or `seq` was never issued by this bus)
+    pub fn since(&self, seq: u64) -> Option<Vec<Arc<Frame>>> {
+        if seq > self.seq {
+            return None;
+        }
+        if seq == self.seq {
+            return Some(Vec::new());
+        }
+        let oldest = self.history.front()?.seq;
+        if oldest > seq + 1 {
+            return None;
+        }
+        Some(
+            self.history
+                .iter()
+                .filter(|frame| frame.seq > seq)
+                .cloned()
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,100 @@
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+
+// Plumbing shared by the command-line tools (`KBQV export-subtitles`, `KBQV export-csv`):
+// argument parsing, resolving a recording file or id to its parts, reading recording
+// lines and writing the result. The tools themselves live in `subtitles.rs`/`export.rs`.
+
+/// Arguments shared by the export CLIs (`export-subtitles`, `export-csv`)
+pub struct CliArgs {
+    pub input: String,
+    pub output: Option<PathBuf>,
+    /// The command's own options in the order given; switches have an empty value
+    pub options: Vec<(String, String)>,
+}
+
+impl CliArgs {
+    // One positional input plus `--output`/`-o` and `--help`/`-h`; `with_value` and
+    // `switches` are the command's own options. Errors end with `usage`.
+    pub fn parse(
+        args: &[String],
+        usage: &str,
+        with_value: &[&str],
+        switches: &[&str],
+    ) -> Result<Self, String> {
+        let mut input: Option<String> = None;
+        let mut output: Option<PathBuf> = None;
+        let mut options = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| format!("{} needs a value\n{}", name, usage))
+            };
+            match arg.as_str() {
+                "--output" | "-o" => output = Some(PathBuf::from(value("--output")?)),
+                "--help" | "-h" => return Err(usage.to_string()),
+                name if with_value.contains(&name) => {
+                    options.push((name.to_string(), value(name)?));
+                }
+                name if switches.contains(&name) => options.push((name.to_string(), String::new())),
+                other if input.is_none() && !other.starts_with('-') => {
+                    input = Some(other.to_string());
+                }
+                other => return Err(format!("Unexpected argument: {}\n{}", other, usage)),
+            }
+        }
+        let input = input.ok_or_else(|| usage.to_string())?;
+        Ok(Self {
+            input,
+            output,
+            options,
+        })
+    }
+
+    // Input files, and where to write: `--output`, else next to the input with `suffix`
+    pub fn files(&self, suffix: &str) -> Result<(Vec<PathBuf>, PathBuf), String> {
+        let (paths, default_output) = cli_input(&self.input, suffix)?;
+        Ok((paths, self.output.clone().unwrap_or(default_output)))
+    }
+}
+
+pub fn write_output(output: &Path, contents: String) -> Result<(), String> {
+    std::fs::write(output, contents)
+        .map_err(|e| format!("Failed to write {}: {}", output.display(), e))
+}
+
+// CLI input: a recording file, or a recording id (all of its parts) from the recordings
+// directory. Also returns the default output: the input without `.jsonl`, plus `suffix`.
+fn cli_input(input: &str, suffix: &str) -> Result<(Vec<PathBuf>, PathBuf), String> {
+    let file = PathBuf::from(input);
+    let (paths, mut output) = if file.is_file() {
+        let output = file.with_extension("").into_os_string();
+        (vec![file], output)
+    } else {
+        let dir = crate::settings::recordings_dir()?;
+        (
+            crate::replay::session_paths(&dir, input)?,
+            dir.join(input).into_os_string(),
+        )
+    };
+    output.push(suffix);
+    Ok((paths, PathBuf::from(output)))
+}
+
+// Every line of the files that parses as `T`, in order
+pub fn read_lines<T: DeserializeOwned>(paths: &[PathBuf]) -> Result<Vec<T>, String> {
+    let mut lines = Vec::new();
+    for path in paths {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read recording: {}", e))?;
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            // A session cut short (crash, full disk) may end in a partial line
+            if let Ok(line) = serde_json::from_str::<T>(line) {
+                lines.push(line);
+            }
+        }
+    }
+    Ok(lines)
+}
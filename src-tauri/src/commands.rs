@@ -0,0 +1,231 @@
+use crate::protocol::{Command, ServerMessage};
+use crate::replay::Replay;
+use crate::state::{AppState, PrivacyConfig, Profile};
+use serde_json::{json, Value};
+use std::time::Instant;
+
+// Runs one client command against the shared state.
+// Ok carries optional data for the `ack`, Err the reason the command was refused.
+pub fn execute(state: &mut AppState, command: &Command) -> Result<Option<Value>, String> {
+    match command {
+        Command::ClearKeys => {
+            state.clear_keys();
+            Ok(None)
+        }
+        Command::PauseCapture => {
+            set_capture_paused(state, true);
+            Ok(None)
+        }
+        Command::ResumeCapture => {
+            set_capture_paused(state, false);
+            Ok(None)
+        }
+        Command::TogglePause => {
+            let paused = !state.capture_paused;
+            set_capture_paused(state, paused);
+            Ok(Some(json!({ "paused": paused })))
+        }
+        Command::SwitchProfile { name } => switch_profile(state, name).map(|_| None),
+        Command::NextProfile => cycle_profile(state, 1),
+        Command::PreviousProfile => cycle_profile(state, -1),
+        Command::ToggleOverlay => {
+            state.overlay_hidden = !state.overlay_hidden;
+            let status = status_message(state);
+            state.broadcast(status);
+            Ok(Some(json!({ "hidden": state.overlay_hidden })))
+        }
+        Command::ResetStats => {
+            state.stats.reset(Instant::now());
+            state.clear_history();
+            Ok(None)
+        }
+        Command::Ping { ts } => Ok(Some(json!({
+            "ts": ts,
+            "server_ts": state.monotonic_ms(Instant::now()),
+        }))),
+    }
+}
+
+// Write lifetime counters if they changed since the last flush (timer and app exit)
+pub fn flush_counters(state: &mut AppState) {
+    if !state.counters.is_dirty() {
+        return;
+    }
+    match crate::settings::save_lifetime_counters(&state.counters) {
+        Ok(()) => state.counters.mark_saved(),
+        Err(error) => eprintln!("[Counters] {}", error),
+    }
+}
+
+pub fn status_message(state: &AppState) -> ServerMessage {
+    ServerMessage::Status {
+        paused: state.capture_paused,
+        masked: state.capture_paused && state.privacy.mask,
+        hidden: state.overlay_hidden,
+        profile: state.profiles.active.clone(),
+        replay: state.replay.is_some(),
+    }
+}
+
+// Replace whatever is on screen (live keys or another replay) with `replay`
+pub fn start_replay(state: &mut AppState, replay: Replay) {
+    state.clear_keys();
+    state.replay = Some(replay);
+    let status = status_message(state);
+    state.broadcast(status);
+}
+
+// Keys still held by the replay are released while it is still flagged as one,
+// so their releases aren't recorded as live input
+pub fn stop_replay(state: &mut AppState) -> bool {
+    if state.replay.is_none() {
+        return false;
+    }
+    state.clear_keys();
+    state.replay = None;
+    let status = status_message(state);
+    state.broadcast(status);
+    true
+}
+
+// Jump within the running replay and put the keys held at that point back on screen
+pub fn seek_replay(state: &mut AppState, position_ms: f64) -> Result<(), String> {
+    let Some(replay) = state.replay.as_mut() else {
+        return Err("No replay running".to_string());
+    };
+    let held = replay.seek(position_ms, Instant::now());
+    state.clear_keys();
+    for press in held {
+        state.apply_replay(press);
+    }
+    Ok(())
+}
+
+pub fn set_capture_paused(state: &mut AppState, paused: bool) {
+    if state.capture_paused == paused {
+        return;
+    }
+    // Drop what is on screen before switching, so releases go out under the
+    // same real/masked code their presses used
+    state.clear_keys();
+    state.capture_paused = paused;
+    let status = status_message(state);
+    state.broadcast(status);
+}
+
+pub fn set_privacy(state: &mut AppState, privacy: PrivacyConfig) {
+    // Masked keys currently shown would otherwise keep the old mask character
+    if state.capture_paused {
+        state.clear_keys();
+    }
+    state.privacy = privacy;
+    state.filter_generation += 1;
+    let _ = crate::settings::save_privacy_config(&state.privacy);
+    let status = status_message(state);
+    state.broadcast(status);
+}
+
+// Store the current target/overlay/style under `name` (replacing a profile with that name)
+pub fn save_profile(state: &mut AppState, name: &str) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name is empty".to_string());
+    }
+    let profile = Profile {
+        name: name.to_string(),
+        target: state.target_config.clone(),
+        overlay: state.app_config.overlay.clone(),
+        key_style: state.app_config.key_style.clone(),
+    };
+    let profiles = &mut state.profiles;
+    match profiles.profiles.iter_mut().find(|p| p.name == name) {
+        Some(existing) => *existing = profile,
+        None => profiles.profiles.push(profile),
+    }
+    profiles.active = Some(name.to_string());
+    let _ = crate::settings::save_profiles_config(profiles);
+
+    let status = status_message(state);
+    state.broadcast(status);
+    Ok(())
+}
+
+pub fn delete_profile(state: &mut AppState, name: &str) -> Result<(), String> {
+    let profiles = &mut state.profiles;
+    let before = profiles.profiles.len();
+    profiles.profiles.retain(|p| p.name != name);
+    if profiles.profiles.len() == before {
+        return Err(format!("Unknown profile: {}", name));
+    }
+    let was_active = profiles.active.as_deref() == Some(name);
+    if was_active {
+        profiles.active = None;
+    }
+    let _ = crate::settings::save_profiles_config(profiles);
+
+    if was_active {
+        let status = status_message(state);
+        state.broadcast(status);
+    }
+    Ok(())
+}
+
+// Apply a saved profile and persist it as the current settings
+pub fn switch_profile(state: &mut AppState, name: &str) -> Result<(), String> {
+    let profile = state
+        .profiles
+        .profiles
+        .iter()
+        .find(|p| p.name == name)
+        .cloned()
+        .ok_or_else(|| format!("Unknown profile: {}", name))?;
+
+    state.target_config = profile.target.clone();
+    state.filter_generation += 1;
+    state.app_config.overlay = profile.overlay.clone();
+    state.app_config.key_style = profile.key_style.clone();
+    state.profiles.active = Some(profile.name.clone());
+    state.clear_keys();
+
+    let _ =
+        crate::settings::save_target_config(&profile.target.mode, profile.target.value.as_deref());
+    let _ = crate::settings::save_overlay(&profile.overlay);
+    let _ = crate::settings::save_key_style_config(&profile.key_style);
+    let _ = crate::settings::save_profiles_config(&state.profiles);
+
+    state.broadcast_transient(ServerMessage::ConfigChanged {
+        overlay: Some(Box::new(profile.overlay)),
+        key_style: Some(Box::new(profile.key_style)),
+        key_images: None,
+    });
+    let status = status_message(state);
+    state.broadcast(status);
+    Ok(())
+}
+
+// Switch to the profile `step` places from the active one (first/last when none is active)
+fn cycle_profile(state: &mut AppState, step: isize) -> Result<Option<Value>, String> {
+    let names: Vec<String> = state
+        .profiles
+        .profiles
+        .iter()
+        .map(|p| p.name.clone())
+        .collect();
+    if names.is_empty() {
+        return Err("No profiles saved".to_string());
+    }
+    let len = names.len() as isize;
+    let current = state
+        .profiles
+        .active
+        .as_ref()
+        .and_then(|active| names.iter().position(|name| name == active));
+    let next = match current {
+        Some(index) => (index as isize + step).rem_euclid(len),
+        None if step > 0 => 0,
+        None => len - 1,
+    };
+    let name = &names[next as usize];
+    switch_profile(state, name)?;
+    Ok(Some(json!({ "profile": name })))
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Lifetime press counters, kept per profile in counters.json so they survive restarts.
+// Presses made while no profile is active go to `default`.
+
+/// Cumulative presses for one profile
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeyCounter {
+    #[serde(default)]
+    pub total: u64,
+    /// Press count per label
+    #[serde(default)]
+    pub per_key: BTreeMap<String, u64>,
+}
+
+impl KeyCounter {
+    // Reset one key (its presses also leave the total) or everything
+    fn reset(&mut self, key: Option<&str>) -> bool {
+        match key {
+            Some(key) => match self.per_key.remove(key) {
+                Some(count) => {
+                    self.total = self.total.saturating_sub(count);
+                    true
+                }
+                None => false,
+            },
+            None => {
+                let changed = self.total > 0 || !self.per_key.is_empty();
+                *self = KeyCounter::default();
+                changed
+            }
+        }
+    }
+}
+
+/// Contents of counters.json
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LifetimeCounters {
+    /// Counter used while no profile is active
+    #[serde(default)]
+    pub default: KeyCounter,
+    /// Counters by profile name
+    #[serde(default)]
+    pub profiles: BTreeMap<String, KeyCounter>,
+    // Changed since the last save
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl LifetimeCounters {
+    pub fn get(&self, profile: Option<&str>) -> Option<&KeyCounter> {
+        match profile {
+            Some(name) => self.profiles.get(name),
+            None => Some(&self.default),
+        }
+    }
+
+    fn get_mut(&mut self, profile: Option<&str>) -> &mut KeyCounter {
+        match profile {
+            Some(name) => self.profiles.entry(name.to_string()).or_default(),
+            None => &mut self.default,
+        }
+    }
+
+    pub fn record(&mut self, profile: Option<&str>, label: &str) {
+        let counter = self.get_mut(profile);
+        counter.total += 1;
+        *counter.per_key.entry(label.to_string()).or_insert(0) += 1;
+        self.dirty = true;
+    }
+
+    // Returns whether anything was cleared
+    pub fn reset(&mut self, profile: Option<&str>, key: Option<&str>) -> bool {
+        let changed = match profile {
+            Some(name) => self
+                .profiles
+                .get_mut(name)
+                .is_some_and(|counter| counter.reset(key)),
+            None => self.default.reset(key),
+        };
+        self.dirty |= changed;
+        changed
+    }
+
+    // True when there is something to save
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    // Call only after the save succeeded, so a failed write is retried on the next flush
+    pub fn mark_saved(&mut self) {
+        self.dirty = false;
+    }
+}
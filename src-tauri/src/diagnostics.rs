@@ -0,0 +1,169 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+// Keyboard hardware diagnostics, run on demand (not persisted): switch chatter
+// (the same physical key pressed again within a few ms of its release) and the
+// largest number of keys seen held at once. Keyed by key code, not label, so
+// left/right variants and masked presses don't mix.
+
+pub const DEFAULT_CHATTER_MS: u64 = 30;
+
+/// Per-key results, most chatter first in the report
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyDiagnostics {
+    pub code: u32,
+    pub label: String,
+    pub presses: u64,
+    pub chatter: u64,
+    /// Shortest release -> press gap seen on this key
+    pub min_gap_ms: Option<f64>,
+}
+
+/// `/api/diagnostics` response
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsReport {
+    pub enabled: bool,
+    pub chatter_ms: u64,
+    /// How long the current run has been going
+    pub elapsed_ms: f64,
+    pub presses: u64,
+    pub chatter: u64,
+    pub max_rollover: usize,
+    /// Keys held when `max_rollover` was reached
+    pub max_rollover_keys: Vec<String>,
+    pub per_key: Vec<KeyDiagnostics>,
+}
+
+#[derive(Default)]
+struct KeyRecord {
+    label: String,
+    presses: u64,
+    chatter: u64,
+    min_gap: Option<Duration>,
+    released_at: Option<Instant>,
+}
+
+pub struct Diagnostics {
+    enabled: bool,
+    chatter_threshold: Duration,
+    started: Instant,
+    keys: HashMap<u32, KeyRecord>,
+    max_rollover: usize,
+    max_rollover_keys: Vec<String>,
+}
+
+impl Diagnostics {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            enabled: false,
+            chatter_threshold: Duration::from_millis(DEFAULT_CHATTER_MS),
+            started: now,
+            keys: HashMap::new(),
+            max_rollover: 0,
+            max_rollover_keys: Vec::new(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    // Starting (or restarting) always begins a fresh run; stopping keeps the results
+    pub fn set_enabled(&mut self, enabled: bool, chatter_ms: u64, now: Instant) {
+        if enabled {
+            *self = Self::new(now);
+            self.chatter_threshold = Duration::from_millis(chatter_ms);
+        }
+        self.enabled = enabled;
+    }
+
+    // `held` is every label physically down after this press
+    pub fn press(&mut self, code: u32, label: &str, held: Vec<String>, now: Instant) {
+        let record = self.keys.entry(code).or_default();
+        record.label = label.to_string();
+        record.presses += 1;
+        if let Some(released) = record.released_at.take() {
+            let gap = now.saturating_duration_since(released);
+            if gap <= self.chatter_threshold {
+                record.chatter += 1;
+            }
+            record.min_gap = Some(record.min_gap.map_or(gap, |min| min.min(gap)));
+        }
+        if held.len() > self.max_rollover {
+            self.max_rollover = held.len();
+            self.max_rollover_keys = held;
+        }
+    }
+
+    pub fn release(&mut self, code: u32, now: Instant) {
+        if let Some(record) = self.keys.get_mut(&code) {
+            record.released_at = Some(now);
+        }
+    }
+
+    pub fn report(&self, now: Instant) -> DiagnosticsReport {
+        let mut per_key: Vec<KeyDiagnostics> = self
+            .keys
+            .iter()
+            .map(|(code, record)| KeyDiagnostics {
+                code: *code,
+                label: record.label.clone(),
+                presses: record.presses,
+                chatter: record.chatter,
+                min_gap_ms: record.min_gap.map(|gap| gap.as_secs_f64() * 1000.0),
+            })
+            .collect();
+        per_key.sort_by(|a, b| {
+            b.chatter
+                .cmp(&a.chatter)
+                .then_with(|| a.label.cmp(&b.label))
+        });
+        DiagnosticsReport {
+            enabled: self.enabled,
+            chatter_ms: self.chatter_threshold.as_millis() as u64,
+            elapsed_ms: now.saturating_duration_since(self.started).as_secs_f64() * 1000.0,
+            presses: per_key.iter().map(|key| key.presses).sum(),
+            chatter: per_key.iter().map(|key| key.chatter).sum(),
+            max_rollover: self.max_rollover,
+            max_rollover_keys: self.max_rollover_keys.clone(),
+            per_key,
+        }
+    }
+}
+
+impl DiagnosticsReport {
+    // Plain-text version for `/api/diagnostics/export`
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "KeyViewer keyboard diagnostics");
+        let _ = writeln!(text, "Duration: {:.1}s", self.elapsed_ms / 1000.0);
+        let _ = writeln!(text, "Chatter threshold: {} ms", self.chatter_ms);
+        let _ = writeln!(text, "Presses: {}", self.presses);
+        let _ = writeln!(text, "Chatter: {}", self.chatter);
+        let _ = writeln!(
+            text,
+            "Max rollover: {} ({})",
+            self.max_rollover,
+            self.max_rollover_keys.join(" + ")
+        );
+        let _ = writeln!(text);
+        let _ = writeln!(
+            text,
+            "{:<12} {:>6} {:>8} {:>8} {:>12}",
+            "KEY", "CODE", "PRESSES", "CHATTER", "MIN GAP MS"
+        );
+        for key in &self.per_key {
+            let min_gap = key
+                .min_gap_ms
+                .map_or("-".to_string(), |gap| format!("{:.1}", gap));
+            let _ = writeln!(
+                text,
+                "{:<12} {:>6} {:>8} {:>8} {:>12}",
+                key.label, key.code, key.presses, key.chatter, min_gap
+            );
+        }
+        text
+    }
+}
@@ -0,0 +1,210 @@
+use crate::cli::{write_output, CliArgs};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+// Session recordings (`recorder.rs`) as CSV for spreadsheets: every event, or one row
+// of statistics per key. Times are ms from the first event, like replay and subtitles.
+// Files start with a UTF-8 BOM so Excel reads non-ASCII window titles correctly.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Table {
+    Events,
+    Keys,
+}
+
+impl Table {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "events" => Some(Table::Events),
+            "keys" => Some(Table::Keys),
+            _ => None,
+        }
+    }
+
+    // Appended to the recording id for the file name
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Table::Events => "-events.csv",
+            Table::Keys => "-keys.csv",
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct Window {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    process: String,
+}
+
+// One recording line, with everything the recorder writes
+#[derive(Deserialize)]
+struct Line {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    ts: f64,
+    #[serde(default)]
+    unix_ms: u64,
+    #[serde(default)]
+    code: u32,
+    #[serde(default)]
+    label: String,
+    #[serde(default)]
+    source: String,
+    #[serde(default)]
+    window: Option<Window>,
+}
+
+#[derive(Default)]
+struct KeyRow {
+    presses: u64,
+    holds: u64,
+    hold_sum: f64,
+    hold_min: Option<f64>,
+    hold_max: Option<f64>,
+    first_ms: f64,
+    last_ms: f64,
+}
+
+// Quoted when needed; text that a spreadsheet would run as a formula (a window titled
+// "=HYPERLINK(...)") gets a leading apostrophe. Single-character key labels like "-" stay.
+fn text(value: &str) -> String {
+    let value = if value.len() > 1 && value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn ms(value: Option<f64>) -> String {
+    value.map_or(String::new(), |value| format!("{:.1}", value))
+}
+
+fn events_csv(lines: &[Line], start: f64) -> String {
+    let mut out = String::from(
+        "type,time_ms,unix_ms,code,label,source,hold_ms,window_title,window_process\n",
+    );
+    let mut pressed: HashMap<u32, f64> = HashMap::new();
+    for line in lines {
+        let hold = match line.kind.as_str() {
+            "press" => {
+                pressed.insert(line.code, line.ts);
+                None
+            }
+            "release" => pressed.remove(&line.code).map(|at| line.ts - at),
+            _ => None,
+        };
+        let window = line.window.as_ref();
+        let _ = writeln!(
+            out,
+            "{},{:.1},{},{},{},{},{},{},{}",
+            line.kind,
+            line.ts - start,
+            line.unix_ms,
+            line.code,
+            text(&line.label),
+            text(&line.source),
+            ms(hold),
+            text(window.map_or("", |w| w.title.as_str())),
+            text(window.map_or("", |w| w.process.as_str())),
+        );
+    }
+    out
+}
+
+fn keys_csv(lines: &[Line], start: f64) -> String {
+    let mut rows: HashMap<String, KeyRow> = HashMap::new();
+    // Code -> (press time, label), so a hold is credited to the label it was pressed as
+    let mut pressed: HashMap<u32, (f64, String)> = HashMap::new();
+    for line in lines {
+        match line.kind.as_str() {
+            "press" => {
+                let at = line.ts - start;
+                let row = rows.entry(line.label.clone()).or_default();
+                if row.presses == 0 {
+                    row.first_ms = at;
+                }
+                row.presses += 1;
+                row.last_ms = at;
+                pressed.insert(line.code, (line.ts, line.label.clone()));
+            }
+            "release" => {
+                let Some((at, label)) = pressed.remove(&line.code) else {
+                    continue;
+                };
+                let hold = line.ts - at;
+                if let Some(row) = rows.get_mut(&label) {
+                    row.holds += 1;
+                    row.hold_sum += hold;
+                    row.hold_min = Some(row.hold_min.map_or(hold, |min| min.min(hold)));
+                    row.hold_max = Some(row.hold_max.map_or(hold, |max| max.max(hold)));
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut rows: Vec<(String, KeyRow)> = rows.into_iter().collect();
+    rows.sort_by(|a, b| b.1.presses.cmp(&a.1.presses).then_with(|| a.0.cmp(&b.0)));
+    let mut out = String::from(
+        "label,count,mean_hold_ms,min_hold_ms,max_hold_ms,first_press_ms,last_press_ms\n",
+    );
+    for (label, row) in rows {
+        let mean = (row.holds > 0).then(|| row.hold_sum / row.holds as f64);
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{:.1},{:.1}",
+            text(&label),
+            row.presses,
+            ms(mean),
+            ms(row.hold_min),
+            ms(row.hold_max),
+            row.first_ms,
+            row.last_ms,
+        );
+    }
+    out
+}
+
+pub fn render(paths: &[PathBuf], table: Table) -> Result<String, String> {
+    let lines: Vec<Line> = crate::cli::read_lines(paths)?;
+    let lines: Vec<Line> = lines
+        .into_iter()
+        .filter(|line| matches!(line.kind.as_str(), "press" | "release" | "repeat"))
+        .collect();
+    let Some(start) = lines.first().map(|line| line.ts) else {
+        return Err("Recording has no events".to_string());
+    };
+    let csv = match table {
+        Table::Events => events_csv(&lines, start),
+        Table::Keys => keys_csv(&lines, start),
+    };
+    Ok(format!("\u{FEFF}{}", csv))
+}
+
+const CLI_USAGE: &str =
+    "Usage: KBQV export-csv <recording id | file.jsonl> [--table events|keys] [--output PATH]";
+
+// `KBQV export-csv ...`: runs instead of the app, like `export-subtitles`.
+// Writes next to the input (`<id>-events.csv`) unless `--output` is given.
+pub fn run_cli(args: &[String]) -> Result<PathBuf, String> {
+    let cli = CliArgs::parse(args, CLI_USAGE, &["--table"], &[])?;
+    let mut table = Table::Events;
+    for (name, value) in &cli.options {
+        if name == "--table" {
+            table = Table::parse(value)
+                .ok_or_else(|| format!("Unknown table: {}\n{}", value, CLI_USAGE))?;
+        }
+    }
+    let (paths, output) = cli.files(table.suffix())?;
+    write_output(&output, render(&paths, table)?)?;
+    Ok(output)
+}
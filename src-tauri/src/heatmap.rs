@@ -0,0 +1,361 @@
+use crate::state::KeyStyleConfig;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write as _;
+
+// Key usage heatmap rendered server-side as SVG (`/api/heatmap.svg`).
+// Counts come from session stats, lifetime counters or the per-minute press log below.
+
+// Press log granularity and how far back it reaches (memory only)
+const LOG_BUCKET_MS: u64 = 60_000;
+const LOG_RETENTION_MS: u64 = 24 * 60 * 60 * 1000;
+
+// Default layout unit (one 1u key) and spacing, in SVG pixels
+const UNIT: f64 = 48.0;
+const KEY_GAP: f64 = 4.0;
+const MARGIN: f64 = 16.0;
+const HEADER_H: f64 = 32.0;
+
+// Platform spellings of the same physical key collapse to one name
+// (left/right stay separate: the layout has both)
+pub fn canonical_label(label: &str) -> String {
+    let label = label.trim().to_uppercase();
+    match label.as_str() {
+        "CMD" | "LCMD" | "LSUPER" => "LWIN".to_string(),
+        "RCMD" | "RSUPER" => "RWIN".to_string(),
+        "LOPT" => "LALT".to_string(),
+        "ROPT" => "RALT".to_string(),
+        _ => label,
+    }
+}
+
+/// Presses per label per minute of wall-clock time, for time-range heatmaps
+#[derive(Default)]
+pub struct PressLog {
+    // (bucket start in unix ms, counts), oldest first
+    buckets: VecDeque<(u64, HashMap<String, u64>)>,
+}
+
+impl PressLog {
+    pub fn record(&mut self, label: &str, unix_ms: u64) {
+        let start = unix_ms - unix_ms % LOG_BUCKET_MS;
+        if self.buckets.back().map(|(at, _)| *at) != Some(start) {
+            self.buckets.push_back((start, HashMap::new()));
+        }
+        if let Some((_, counts)) = self.buckets.back_mut() {
+            *counts.entry(canonical_label(label)).or_insert(0) += 1;
+        }
+        while self
+            .buckets
+            .front()
+            .is_some_and(|(at, _)| unix_ms.saturating_sub(*at) > LOG_RETENTION_MS)
+        {
+            self.buckets.pop_front();
+        }
+    }
+
+    // Minutes overlapping [from, to) are counted whole
+    pub fn counts(&self, from_ms: u64, to_ms: u64) -> HashMap<String, u64> {
+        let mut totals = HashMap::new();
+        for (start, counts) in &self.buckets {
+            if *start + LOG_BUCKET_MS <= from_ms || *start >= to_ms {
+                continue;
+            }
+            for (label, count) in counts {
+                *totals.entry(label.clone()).or_insert(0) += count;
+            }
+        }
+        totals
+    }
+}
+
+pub struct HeatCell {
+    pub label: String,
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+}
+
+pub struct HeatLayout {
+    pub cells: Vec<HeatCell>,
+    pub width: f64,
+    pub height: f64,
+}
+
+// Rows of (label, width in units); empty labels are spacing
+const ANSI_ROWS: [&[(&str, f64)]; 6] = [
+    &[
+        ("ESC", 1.0),
+        ("", 1.0),
+        ("F1", 1.0),
+        ("F2", 1.0),
+        ("F3", 1.0),
+        ("F4", 1.0),
+        ("", 0.5),
+        ("F5", 1.0),
+        ("F6", 1.0),
+        ("F7", 1.0),
+        ("F8", 1.0),
+        ("", 0.5),
+        ("F9", 1.0),
+        ("F10", 1.0),
+        ("F11", 1.0),
+        ("F12", 1.0),
+        ("", 0.25),
+        ("PRINT", 1.0),
+        ("SCROLL", 1.0),
+        ("PAUSE", 1.0),
+    ],
+    &[
+        ("`", 1.0),
+        ("1", 1.0),
+        ("2", 1.0),
+        ("3", 1.0),
+        ("4", 1.0),
+        ("5", 1.0),
+        ("6", 1.0),
+        ("7", 1.0),
+        ("8", 1.0),
+        ("9", 1.0),
+        ("0", 1.0),
+        ("-", 1.0),
+        ("=", 1.0),
+        ("BKSP", 2.0),
+        ("", 0.25),
+        ("INS", 1.0),
+        ("HOME", 1.0),
+        ("PG UP", 1.0),
+    ],
+    &[
+        ("TAB", 1.5),
+        ("Q", 1.0),
+        ("W", 1.0),
+        ("E", 1.0),
+        ("R", 1.0),
+        ("T", 1.0),
+        ("Y", 1.0),
+        ("U", 1.0),
+        ("I", 1.0),
+        ("O", 1.0),
+        ("P", 1.0),
+        ("[", 1.0),
+        ("]", 1.0),
+        ("\\", 1.5),
+        ("", 0.25),
+        ("DEL", 1.0),
+        ("END", 1.0),
+        ("PG DN", 1.0),
+    ],
+    &[
+        ("CAPS", 1.75),
+        ("A", 1.0),
+        ("S", 1.0),
+        ("D", 1.0),
+        ("F", 1.0),
+        ("G", 1.0),
+        ("H", 1.0),
+        ("J", 1.0),
+        ("K", 1.0),
+        ("L", 1.0),
+        (";", 1.0),
+        ("'", 1.0),
+        ("ENTER", 2.25),
+    ],
+    &[
+        ("LSHIFT", 2.25),
+        ("Z", 1.0),
+        ("X", 1.0),
+        ("C", 1.0),
+        ("V", 1.0),
+        ("B", 1.0),
+        ("N", 1.0),
+        ("M", 1.0),
+        (",", 1.0),
+        (".", 1.0),
+        ("/", 1.0),
+        ("RSHIFT", 2.75),
+        ("", 1.25),
+        ("UP", 1.0),
+    ],
+    &[
+        ("LCTRL", 1.25),
+        ("LWIN", 1.25),
+        ("LALT", 1.25),
+        ("SPACE", 6.25),
+        ("RALT", 1.25),
+        ("RWIN", 1.25),
+        ("", 1.25),
+        ("RCTRL", 1.25),
+        ("", 0.25),
+        ("LEFT", 1.0),
+        ("DOWN", 1.0),
+        ("RIGHT", 1.0),
+    ],
+];
+
+// Full-size ANSI keyboard without the numpad
+pub fn ansi_layout() -> HeatLayout {
+    let mut cells = Vec::new();
+    let mut width: f64 = 0.0;
+    for (row, keys) in ANSI_ROWS.iter().enumerate() {
+        // Gap under the function row, like a real board
+        let y = row as f64 * UNIT + if row > 0 { UNIT / 4.0 } else { 0.0 };
+        let mut x = 0.0;
+        for (label, units) in keys.iter() {
+            if !label.is_empty() {
+                cells.push(HeatCell {
+                    label: label.to_string(),
+                    x,
+                    y,
+                    w: units * UNIT,
+                    h: UNIT,
+                });
+            }
+            x += units * UNIT;
+        }
+        width = width.max(x);
+    }
+    HeatLayout {
+        cells,
+        width,
+        height: ANSI_ROWS.len() as f64 * UNIT + UNIT / 4.0,
+    }
+}
+
+// The Key Viewer canvas layout, minus cells that only show a statistic
+pub fn key_viewer_layout(style: &KeyStyleConfig) -> Option<HeatLayout> {
+    let cells: Vec<HeatCell> = style
+        .key_viewer_layout
+        .iter()
+        .filter(|cell| !matches!(cell.stat.as_deref(), Some(stat) if stat != "count"))
+        .map(|cell| HeatCell {
+            label: cell.key.clone(),
+            x: cell.x,
+            y: cell.y,
+            w: cell.w,
+            h: cell.h,
+        })
+        .collect();
+    if cells.is_empty() {
+        return None;
+    }
+    Some(HeatLayout {
+        cells,
+        width: style.kv_canvas_w,
+        height: style.kv_canvas_h,
+    })
+}
+
+// Cold -> hot color stops
+const STOPS: [(f64, [u8; 3]); 4] = [
+    (0.0, [0x1e, 0x3a, 0x8a]),
+    (0.4, [0x25, 0x63, 0xeb]),
+    (0.7, [0xf5, 0x9e, 0x0b]),
+    (1.0, [0xef, 0x44, 0x44]),
+];
+const UNUSED_COLOR: &str = "#1e293b";
+
+fn heat_color(t: f64) -> String {
+    let t = t.clamp(0.0, 1.0);
+    let upper = STOPS
+        .iter()
+        .position(|(at, _)| *at >= t)
+        .unwrap_or(STOPS.len() - 1)
+        .max(1);
+    let (a_at, a) = STOPS[upper - 1];
+    let (b_at, b) = STOPS[upper];
+    let f = (t - a_at) / (b_at - a_at);
+    let mix = |i: usize| (a[i] as f64 + (b[i] as f64 - a[i] as f64) * f).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", mix(0), mix(1), mix(2))
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// `counts` keys must already be canonical labels
+pub fn render_svg(layout: &HeatLayout, counts: &HashMap<String, u64>, title: &str) -> String {
+    let on_layout: HashMap<String, u64> = layout
+        .cells
+        .iter()
+        .map(|cell| {
+            let label = canonical_label(&cell.label);
+            let count = counts.get(&label).copied().unwrap_or(0);
+            (label, count)
+        })
+        .collect();
+    let max = on_layout.values().copied().max().unwrap_or(0);
+    let total: u64 = counts.values().sum();
+    // Pressed keys the layout has no cell for (mouse buttons, numpad, ...)
+    let mut missing: Vec<(&String, &u64)> = counts
+        .iter()
+        .filter(|(label, _)| !on_layout.contains_key(*label))
+        .collect();
+    missing.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+    let footer_h = if missing.is_empty() { 0.0 } else { HEADER_H };
+    let width = layout.width + MARGIN * 2.0;
+    let height = layout.height + MARGIN * 2.0 + HEADER_H + footer_h;
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="ui-sans-serif, system-ui, 'Segoe UI', Roboto, Arial">"#,
+        w = width,
+        h = height
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect width="100%" height="100%" fill="#0f172a"/>"##
+    );
+    let _ = writeln!(
+        svg,
+        r##"<text x="{x}" y="{y}" fill="#e2e8f0" font-size="15" font-weight="700">{title} · {total} presses</text>"##,
+        x = MARGIN,
+        y = MARGIN + 14.0,
+        title = escape_xml(title),
+        total = total
+    );
+    for cell in &layout.cells {
+        let count = on_layout[&canonical_label(&cell.label)];
+        // Square root scale so lightly used keys still show some heat
+        let fill = if count == 0 {
+            UNUSED_COLOR.to_string()
+        } else {
+            heat_color((count as f64 / max as f64).sqrt())
+        };
+        let (x, y) = (
+            cell.x + MARGIN + KEY_GAP / 2.0,
+            cell.y + MARGIN + HEADER_H + KEY_GAP / 2.0,
+        );
+        let (w, h) = ((cell.w - KEY_GAP).max(1.0), (cell.h - KEY_GAP).max(1.0));
+        let font = (h * 0.28).clamp(8.0, 16.0).round();
+        let _ = writeln!(
+            svg,
+            r##"<g><rect x="{x}" y="{y}" width="{w}" height="{h}" rx="6" fill="{fill}" stroke="#334155"/><text x="{cx}" y="{ly}" fill="#f8fafc" font-size="{font}" font-weight="700" text-anchor="middle">{label}</text><text x="{cx}" y="{cy}" fill="#cbd5e1" font-size="{small}" text-anchor="middle">{count}</text></g>"##,
+            cx = x + w / 2.0,
+            ly = y + h * 0.45,
+            cy = y + h * 0.8,
+            small = (font * 0.8).round(),
+            label = escape_xml(&cell.label),
+        );
+    }
+    if !missing.is_empty() {
+        let list: Vec<String> = missing
+            .iter()
+            .take(12)
+            .map(|(label, count)| format!("{} {}", escape_xml(label), count))
+            .collect();
+        let _ = writeln!(
+            svg,
+            r##"<text x="{x}" y="{y}" fill="#94a3b8" font-size="12">Not on layout: {list}</text>"##,
+            x = MARGIN,
+            y = height - MARGIN,
+            list = list.join(", ")
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
@@ -0,0 +1,99 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Recent-press history: unlike `keys` (held only), entries stay after release for a
+// linger time, so a quick tap is still readable on stream.
+
+/// One press, or a run of back-to-back taps of the same key
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    /// Stable across updates, so clients can animate entries in and out
+    pub id: u64,
+    pub label: String,
+    /// Time of the latest press (monotonic ms)
+    pub ts: f64,
+    /// Hold duration of the latest press; `None` while still held
+    pub held_ms: Option<f64>,
+    /// Taps merged into this entry
+    pub count: u32,
+    #[serde(skip)]
+    code: u32,
+    #[serde(skip)]
+    released_at: Option<Instant>,
+}
+
+#[derive(Default)]
+pub struct KeyHistory {
+    entries: VecDeque<HistoryEntry>,
+    next_id: u64,
+}
+
+impl KeyHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> Vec<HistoryEntry> {
+        self.entries.iter().cloned().collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // A tap of the key shown last (already released) counts up that entry instead of adding one
+    pub fn press(&mut self, code: u32, label: &str, ts: f64, max_entries: usize) {
+        if let Some(last) = self.entries.back_mut() {
+            if last.label == label && last.released_at.is_some() {
+                last.code = code;
+                last.ts = ts;
+                last.held_ms = None;
+                last.count += 1;
+                last.released_at = None;
+                return;
+            }
+        }
+        self.next_id += 1;
+        self.entries.push_back(HistoryEntry {
+            id: self.next_id,
+            label: label.to_string(),
+            ts,
+            held_ms: None,
+            count: 1,
+            code,
+            released_at: None,
+        });
+        while self.entries.len() > max_entries.max(1) {
+            self.entries.pop_front();
+        }
+    }
+
+    // Returns false when the key had no held entry (e.g. it already scrolled out)
+    pub fn release(&mut self, code: u32, held_ms: f64, now: Instant) -> bool {
+        let entry = self
+            .entries
+            .iter_mut()
+            .rev()
+            .find(|entry| entry.code == code && entry.released_at.is_none());
+        match entry {
+            Some(entry) => {
+                entry.held_ms = Some(held_ms);
+                entry.released_at = Some(now);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Drop entries released more than `linger` ago; true if any were removed
+    pub fn expire(&mut self, linger: Duration, now: Instant) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|entry| {
+            entry
+                .released_at
+                .is_none_or(|at| now.saturating_duration_since(at) < linger)
+        });
+        self.entries.len() != before
+    }
+}
@@ -0,0 +1,118 @@
+use crate::protocol::Command;
+use crate::state::{AppState, HotkeyAction, HotkeysConfig};
+use parking_lot::RwLock;
+use std::collections::{HashMap, HashSet};
+
+// Global hotkeys are read from the same input stream the overlay uses (nothing is
+// consumed or registered with the OS), before target-window and pause filtering.
+
+// Left/right and per-platform modifier labels collapse to one name
+pub fn normalize_label(label: &str) -> String {
+    let label = label.trim().to_uppercase();
+    match label.as_str() {
+        "LCTRL" | "RCTRL" | "CONTROL" => "CTRL".to_string(),
+        "LSHIFT" | "RSHIFT" => "SHIFT".to_string(),
+        "LALT" | "RALT" | "LOPT" | "ROPT" | "OPT" => "ALT".to_string(),
+        "LSUPER" | "RSUPER" | "LWIN" | "RWIN" | "CMD" | "WIN" | "SUPER" => "META".to_string(),
+        _ => label,
+    }
+}
+
+pub fn is_modifier(label: &str) -> bool {
+    matches!(label, "CTRL" | "SHIFT" | "ALT" | "META")
+}
+
+// Whether a key has to be watched for hotkeys at all (lets the Windows poller skip the rest)
+#[cfg(target_os = "windows")]
+pub fn is_hotkey_label(label: &str, config: &HotkeysConfig) -> bool {
+    let label = normalize_label(label);
+    config.enabled
+        && (is_modifier(&label)
+            || config
+                .bindings
+                .iter()
+                .any(|binding| binding.keys.contains(&label)))
+}
+
+/// Physical keys currently held, tracked independently of `AppState`
+/// so chords still work while paused or outside the target window
+#[derive(Default)]
+pub struct HotkeyWatcher {
+    held: HashMap<u32, String>,
+    // Keys of a chord that fired, kept off the overlay until released
+    suppressed: HashSet<u32>,
+}
+
+impl HotkeyWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Returns the action whose chord this press completes
+    pub fn press(
+        &mut self,
+        code: u32,
+        label: &str,
+        config: &HotkeysConfig,
+    ) -> Option<HotkeyAction> {
+        let label = normalize_label(label);
+        // Auto-repeat of a held key doesn't fire again
+        if self.held.insert(code, label.clone()).is_some() || !config.enabled {
+            return None;
+        }
+        let binding = config.bindings.iter().find(|binding| {
+            let chord = &binding.keys;
+            let complete = chord.contains(&label)
+                && chord
+                    .iter()
+                    .all(|key| self.held.values().any(|held| held == key));
+            // Extra modifiers make it a different chord (Ctrl+Shift+Alt+P is not Ctrl+Shift+P)
+            let extra_modifier = self
+                .held
+                .values()
+                .any(|held| is_modifier(held) && !chord.contains(held));
+            complete && !extra_modifier
+        })?;
+        if config.suppress_chord {
+            let chord_codes = self
+                .held
+                .iter()
+                .filter(|(_, held)| binding.keys.contains(held))
+                .map(|(code, _)| *code);
+            self.suppressed.extend(chord_codes);
+        }
+        Some(binding.action)
+    }
+
+    pub fn release(&mut self, code: u32) {
+        self.held.remove(&code);
+        self.suppressed.remove(&code);
+    }
+
+    // Press belongs to a fired chord and must not reach the overlay
+    pub fn is_suppressed(&self, code: u32) -> bool {
+        self.suppressed.contains(&code)
+    }
+}
+
+fn command_for(action: HotkeyAction) -> Command {
+    match action {
+        HotkeyAction::TogglePause => Command::TogglePause,
+        HotkeyAction::ClearKeys => Command::ClearKeys,
+        HotkeyAction::NextProfile => Command::NextProfile,
+        HotkeyAction::PreviousProfile => Command::PreviousProfile,
+        HotkeyAction::ToggleOverlay => Command::ToggleOverlay,
+    }
+}
+
+// Take the chord's keys that already reached the overlay (its modifiers) back off,
+// then run the action like the matching /ws command
+pub fn run(state: &RwLock<AppState>, action: HotkeyAction, watcher: &HotkeyWatcher) {
+    let mut state_lock = state.write();
+    for code in &watcher.suppressed {
+        state_lock.remove_key(*code);
+    }
+    if let Err(error) = crate::commands::execute(&mut state_lock, &command_for(action)) {
+        eprintln!("[Hotkeys] {:?} failed: {}", action, error);
+    }
+}
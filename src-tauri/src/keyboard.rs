@@ -1,12 +1,17 @@
-use crate::state::AppState;
+use crate::hotkeys::{self, HotkeyWatcher};
+use crate::state::{AppState, BlockRule};
 use crate::window_info;
 use parking_lot::RwLock;
 use std::sync::Arc;
 #[allow(unused_imports)]
 use std::time::Duration;
 
+#[cfg(target_os = "linux")]
+use std::collections::HashSet;
 #[cfg(target_os = "linux")]
 use std::sync::mpsc;
+#[cfg(target_os = "linux")]
+use std::time::Instant;
 
 // Linux uses hook-based input
 #[cfg(target_os = "linux")]
@@ -23,6 +28,12 @@ use rdev::Key;
 #[cfg(target_os = "windows")]
 const KEY_POLLING_INTERVAL_MS: u64 = 16;
 
+// How long a foreground window lookup is reused for the next events (Linux).
+// Each lookup is an X11 round trip; a new window can't take input this quickly after
+// a switch, so reusing it within a burst of keys doesn't let a blocked window through.
+#[cfg(target_os = "linux")]
+const FOREGROUND_REUSE: Duration = Duration::from_millis(50);
+
 // Event types for internal processing (Linux hook-based)
 #[cfg(target_os = "linux")]
 #[derive(Debug, Clone)]
@@ -312,6 +323,27 @@ pub fn start_keyboard_hook(state: Arc<RwLock<AppState>>) {
     // Track modifier state with atomic
     static MODIFIER_STATE: AtomicU64 = AtomicU64::new(0);
 
+    // Whether a FlagsChanged event for `keycode` is a press (flag set) or a release
+    fn modifier_is_pressed(keycode: i64, flags: CGEventFlags) -> bool {
+        match keycode {
+            // Left Shift (56), Right Shift (60)
+            56 | 60 => flags.contains(CGEventFlags::CGEventFlagShift),
+            // Left Cmd (55), Right Cmd (54)
+            55 | 54 => flags.contains(CGEventFlags::CGEventFlagCommand),
+            // Left Opt (58), Right Opt (61)
+            58 | 61 => flags.contains(CGEventFlags::CGEventFlagAlternate),
+            // Left Ctrl (59), Right Ctrl (62)
+            59 | 62 => flags.contains(CGEventFlags::CGEventFlagControl),
+            // Caps Lock (57)
+            57 => flags.contains(CGEventFlags::CGEventFlagAlphaShift),
+            // Fn (63)
+            63 => flags.contains(CGEventFlags::CGEventFlagSecondaryFn),
+            _ => false,
+        }
+    }
+
+    let hotkey_watcher = parking_lot::Mutex::new(HotkeyWatcher::new());
+
     let state_ptr = Arc::into_raw(state) as *mut std::ffi::c_void;
 
     let callback = Box::new(
@@ -323,19 +355,55 @@ pub fn start_keyboard_hook(state: Arc<RwLock<AppState>>) {
             let state_clone = Arc::clone(&state);
             std::mem::forget(state); // Don't drop the Arc
 
-            // Check target config first
-            let target_config = {
+            // Hotkeys see every key, whatever the target window or pause state
+            let keycode = event.get_integer_value_field(EventField::KEYBOARD_EVENT_KEYCODE);
+            let hotkey_press = match event_type {
+                CGEventType::KeyDown => Some(true),
+                CGEventType::KeyUp => Some(false),
+                CGEventType::FlagsChanged => Some(modifier_is_pressed(keycode, event.get_flags())),
+                _ => None,
+            };
+            let mut watcher = hotkey_watcher.lock();
+            let action = match hotkey_press {
+                Some(true) => watcher.press(
+                    keycode as u32,
+                    &keycode_to_label(keycode as u16),
+                    &state_clone.read().hotkeys,
+                ),
+                Some(false) => {
+                    watcher.release(keycode as u32);
+                    None
+                }
+                None => None,
+            };
+            if let Some(action) = action {
+                hotkeys::run(&state_clone, action, &watcher);
+            }
+            // Part of a hotkey chord that already fired
+            let suppressed = watcher.is_suppressed(keycode as u32);
+            drop(watcher);
+
+            // Check target config (and privacy blocklist) first
+            let (target_config, blocklist) = {
                 let state_lock = state_clone.read();
-                state_lock.target_config.clone()
+                (
+                    state_lock.target_config.clone(),
+                    state_lock.privacy.blocklist.clone(),
+                )
             };
 
-            if !should_process_event(&target_config) {
+            if !should_process_event(&target_config, &blocklist) || suppressed {
                 return Some(event.to_owned());
             }
 
             match event_type {
                 CGEventType::KeyDown => {
                     let keycode = event.get_integer_value_field(EventField::KEYBOARD_EVENT_KEYCODE);
+                    // Auto-repeat is flagged, not treated as another press
+                    if event.get_integer_value_field(EventField::KEYBOARD_EVENT_AUTOREPEAT) != 0 {
+                        state_clone.write().key_repeat(keycode as u32);
+                        return Some(event.to_owned());
+                    }
                     let label = keycode_to_label(keycode as u16);
                     eprintln!("[Keyboard Hook] Key down: {} (keycode {})", label, keycode);
 
@@ -363,21 +431,7 @@ pub fn start_keyboard_hook(state: Arc<RwLock<AppState>>) {
                     );
 
                     // Determine if key was pressed or released based on flag changes
-                    let is_pressed = match keycode {
-                        // Left Shift (56), Right Shift (60)
-                        56 | 60 => flags.contains(CGEventFlags::CGEventFlagShift),
-                        // Left Cmd (55), Right Cmd (54)
-                        55 | 54 => flags.contains(CGEventFlags::CGEventFlagCommand),
-                        // Left Opt (58), Right Opt (61)
-                        58 | 61 => flags.contains(CGEventFlags::CGEventFlagAlternate),
-                        // Left Ctrl (59), Right Ctrl (62)
-                        59 | 62 => flags.contains(CGEventFlags::CGEventFlagControl),
-                        // Caps Lock (57)
-                        57 => flags.contains(CGEventFlags::CGEventFlagAlphaShift),
-                        // Fn (63)
-                        63 => flags.contains(CGEventFlags::CGEventFlagSecondaryFn),
-                        _ => false,
-                    };
+                    let is_pressed = modifier_is_pressed(keycode, flags);
 
                     let mut state_lock = state_clone.write();
                     if is_pressed {
@@ -494,14 +548,41 @@ pub fn start_keyboard_hook(state: Arc<RwLock<AppState>>) {
     eprintln!("[Keyboard Hook] Listener stopped unexpectedly");
 }
 
+// Foreground window of the latest lookup, reused for `FOREGROUND_REUSE` (Linux only)
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+struct ForegroundCache {
+    window: Option<window_info::WindowInfo>,
+    at: Option<Instant>,
+}
+
+#[cfg(target_os = "linux")]
+impl ForegroundCache {
+    fn get(&mut self) -> Option<window_info::WindowInfo> {
+        let now = Instant::now();
+        let fresh = self
+            .at
+            .is_some_and(|at| now.saturating_duration_since(at) < FOREGROUND_REUSE);
+        if !fresh {
+            self.window = window_info::get_foreground_window();
+            self.at = Some(now);
+        }
+        self.window.clone()
+    }
+}
+
 // Process input events in a separate thread (Linux only)
 #[cfg(target_os = "linux")]
 fn process_input_events(rx: mpsc::Receiver<InputEvent>, state: Arc<RwLock<AppState>>) {
-    // Cache for target config to reduce lock contention
+    // Copy of the target config and blocklist, reloaded whenever `filter_generation` moves
     let mut cached_target_mode = String::new();
     let mut cached_target_value: Option<String> = None;
-    let mut config_check_counter = 0u32;
-    const CONFIG_REFRESH_INTERVAL: u32 = 50; // Refresh config every N events
+    let mut cached_blocklist: Vec<BlockRule> = Vec::new();
+    let mut cached_generation: Option<u64> = None;
+    let mut foreground = ForegroundCache::default();
+    let mut hotkey_watcher = HotkeyWatcher::new();
+    // X11 delivers auto-repeat as more KeyPress events for a key that is still down
+    let mut held_keys: HashSet<u32> = HashSet::new();
 
     loop {
         // Wait for next event
@@ -513,19 +594,59 @@ fn process_input_events(rx: mpsc::Receiver<InputEvent>, state: Arc<RwLock<AppSta
             }
         };
 
-        // Periodically refresh cached target config
-        config_check_counter += 1;
-        if config_check_counter >= CONFIG_REFRESH_INTERVAL || cached_target_mode.is_empty() {
-            config_check_counter = 0;
+        // A new blocklist rule or target applies from the very next event
+        {
             let state_lock = state.read();
-            cached_target_mode = state_lock.target_config.mode.clone();
-            cached_target_value = state_lock.target_config.value.clone();
+            if cached_generation != Some(state_lock.filter_generation) {
+                cached_generation = Some(state_lock.filter_generation);
+                cached_target_mode = state_lock.target_config.mode.clone();
+                cached_target_value = state_lock.target_config.value.clone();
+                cached_blocklist = state_lock.privacy.blocklist.clone();
+            }
+        }
+
+        // Auto-repeat is flagged, not treated as another press. There is no evdev
+        // backend (which reports repeats as value 2); rdev on X11 gives no repeat flag,
+        // so a press of a key that is still down counts as one.
+        let repeat = match &event {
+            InputEvent::KeyPress { code, .. } => !held_keys.insert(*code),
+            InputEvent::KeyRelease { code } => {
+                held_keys.remove(code);
+                false
+            }
+            _ => false,
+        };
+
+        // Hotkeys see every key, whatever the target window or pause state
+        match &event {
+            InputEvent::KeyPress { code, label } if !repeat => {
+                let action = hotkey_watcher.press(*code, label, &state.read().hotkeys);
+                if let Some(action) = action {
+                    hotkeys::run(&state, action, &hotkey_watcher);
+                }
+            }
+            InputEvent::KeyRelease { code } => hotkey_watcher.release(*code),
+            _ => {}
         }
 
         match event {
             InputEvent::KeyPress { code, label } => {
                 // Check if target window matches (uses cached config)
-                if !should_process_event_cached(&cached_target_mode, &cached_target_value) {
+                if !should_process_event_cached(
+                    &cached_target_mode,
+                    &cached_target_value,
+                    &cached_blocklist,
+                    || foreground.get(),
+                ) {
+                    continue;
+                }
+                // Part of a hotkey chord that already fired
+                if hotkey_watcher.is_suppressed(code) {
+                    continue;
+                }
+                // Same target and blocklist gate as the press itself
+                if repeat {
+                    state.write().key_repeat(code);
                     continue;
                 }
 
@@ -547,7 +668,12 @@ fn process_input_events(rx: mpsc::Receiver<InputEvent>, state: Arc<RwLock<AppSta
                 }
             }
             InputEvent::ButtonPress { code, label } => {
-                if !should_process_event_cached(&cached_target_mode, &cached_target_value) {
+                if !should_process_event_cached(
+                    &cached_target_mode,
+                    &cached_target_value,
+                    &cached_blocklist,
+                    || foreground.get(),
+                ) {
                     continue;
                 }
 
@@ -574,15 +700,49 @@ fn process_input_events(rx: mpsc::Receiver<InputEvent>, state: Arc<RwLock<AppSta
     }
 }
 
+// Privacy blocklist check; process names compare without a trailing ".exe"
+fn is_blocked(blocklist: &[BlockRule], window: &window_info::WindowInfo) -> bool {
+    fn process_stem(name: &str) -> String {
+        let name = name.to_lowercase();
+        name.strip_suffix(".exe").unwrap_or(&name).to_string()
+    }
+
+    blocklist.iter().any(|rule| match rule.mode.as_str() {
+        "process" => process_stem(&window.process) == process_stem(&rule.value),
+        "title" => window
+            .title
+            .to_lowercase()
+            .contains(&rule.value.to_lowercase()),
+        "class" => window.class.to_lowercase() == rule.value.to_lowercase(),
+        _ => false,
+    })
+}
+
 // Optimized version that uses cached config (reduces lock contention)
+// A blocklisted foreground window overrides every target mode
+// `foreground` looks up the foreground window, only when the mode needs it
 #[cfg(not(target_os = "macos"))]
-fn should_process_event_cached(mode: &str, value: &Option<String>) -> bool {
+fn should_process_event_cached(
+    mode: &str,
+    value: &Option<String>,
+    blocklist: &[BlockRule],
+    foreground: impl FnOnce() -> Option<window_info::WindowInfo>,
+) -> bool {
     match mode {
         "disabled" => false,
-        "all" => true,
+        // Only look up the foreground window when there is something to block
+        "all" => {
+            blocklist.is_empty()
+                || foreground()
+                    .map(|window_info| !is_blocked(blocklist, &window_info))
+                    .unwrap_or(true)
+        }
         "title" | "process" | "hwnd" | "class" => {
             // Get foreground window info and check if it matches
-            if let Some(window_info) = window_info::get_foreground_window() {
+            if let Some(window_info) = foreground() {
+                if is_blocked(blocklist, &window_info) {
+                    return false;
+                }
                 match mode {
                     "title" => {
                         if let Some(v) = value {
@@ -623,7 +783,10 @@ fn should_process_event_cached(mode: &str, value: &Option<String>) -> bool {
 }
 
 #[cfg(target_os = "macos")]
-fn should_process_event(target_config: &crate::state::TargetConfig) -> bool {
+fn should_process_event(
+    target_config: &crate::state::TargetConfig,
+    blocklist: &[BlockRule],
+) -> bool {
     let mode = target_config.mode.as_str();
 
     match mode {
@@ -632,8 +795,18 @@ fn should_process_event(target_config: &crate::state::TargetConfig) -> bool {
             false
         }
         "all" => {
-            eprintln!("[Keyboard Hook] Mode is 'all' - accepting event");
-            true
+            let blocked = !blocklist.is_empty()
+                && window_info::get_foreground_window()
+                    .map(|window_info| is_blocked(blocklist, &window_info))
+                    .unwrap_or(false);
+            if blocked {
+                eprintln!(
+                    "[Keyboard Hook] Mode is 'all' but window is blocklisted - ignoring event"
+                );
+            } else {
+                eprintln!("[Keyboard Hook] Mode is 'all' - accepting event");
+            }
+            !blocked
         }
         "title" | "process" | "hwnd" | "class" => {
             // Get foreground window info and check if it matches
@@ -643,6 +816,11 @@ fn should_process_event(target_config: &crate::state::TargetConfig) -> bool {
                     window_info.title, window_info.process
                 );
 
+                if is_blocked(blocklist, &window_info) {
+                    eprintln!("[Keyboard Hook] Window is blocklisted - ignoring event");
+                    return false;
+                }
+
                 let result = match mode {
                     "title" => {
                         if let Some(value) = &target_config.value {
@@ -817,6 +995,8 @@ fn validate_key_state_loop(state: Arc<RwLock<AppState>>) {
 
     // Track which VK codes are currently "pressed" according to our state
     let mut polling_state: std::collections::HashMap<i32, bool> = std::collections::HashMap::new();
+    // Hotkey keys are polled separately, before the target/disabled checks below
+    let mut hotkey_watcher = HotkeyWatcher::new();
 
     eprintln!(
         "[Key Poller] Starting pure polling mode ({}ms interval)",
@@ -826,10 +1006,33 @@ fn validate_key_state_loop(state: Arc<RwLock<AppState>>) {
     loop {
         std::thread::sleep(Duration::from_millis(KEY_POLLING_INTERVAL_MS));
 
+        // Same codes as the tracked keys below, so a fired chord can be taken off the overlay
+        let mut fired = Vec::new();
+        {
+            let s = state.read();
+            for &(vk, label) in MONITORED_KEYS {
+                let code = vk as u32 | 0x80000000;
+                if !hotkeys::is_hotkey_label(label, &s.hotkeys) {
+                    // Bindings may have changed; don't keep a stale hold around
+                    hotkey_watcher.release(code);
+                    continue;
+                }
+                let is_down = (unsafe { GetAsyncKeyState(vk) } as u16 & 0x8000) != 0;
+                if !is_down {
+                    hotkey_watcher.release(code);
+                } else if let Some(action) = hotkey_watcher.press(code, label, &s.hotkeys) {
+                    fired.push(action);
+                }
+            }
+        }
+        for action in fired {
+            hotkeys::run(&state, action, &hotkey_watcher);
+        }
+
         // Check target window filter
-        let target_config = {
+        let (target_config, blocklist) = {
             let s = state.read();
-            s.target_config.clone()
+            (s.target_config.clone(), s.privacy.blocklist.clone())
         };
 
         // Skip if disabled
@@ -843,12 +1046,13 @@ fn validate_key_state_loop(state: Arc<RwLock<AppState>>) {
             continue;
         }
 
-        // Check if current window matches target (for non-"all" modes)
-        let should_track = if target_config.mode == "all" {
-            true
-        } else {
-            should_process_event_cached(&target_config.mode, &target_config.value)
-        };
+        // Check if current window matches target and isn't blocklisted
+        let should_track = should_process_event_cached(
+            &target_config.mode,
+            &target_config.value,
+            &blocklist,
+            window_info::get_foreground_window,
+        );
 
         if !should_track {
             // Clear keys when not in target window
@@ -895,6 +1099,11 @@ fn validate_key_state_loop(state: Arc<RwLock<AppState>>) {
             for (vk, label) in &keys_to_add {
                 // Use VK code as the key code for polling-based tracking
                 let code = *vk as u32 | 0x80000000; // High bit set to distinguish from hook codes
+
+                // Part of a hotkey chord that already fired
+                if hotkey_watcher.is_suppressed(code) {
+                    continue;
+                }
                 state_lock.add_key(code, label.to_string());
             }
 
@@ -1,10 +1,27 @@
 // Prevents additional console window on Windows in release builds
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod bus;
+mod cli;
+mod commands;
+mod counters;
+mod diagnostics;
+mod export;
+mod heatmap;
+mod history;
+mod hotkeys;
 mod keyboard;
+mod protocol;
+mod recorder;
+mod replay;
+mod rhythm;
 mod server;
 mod settings;
 mod state;
+mod stats;
+mod strafe;
+mod subtitles;
+mod typed_text;
 mod window_info;
 
 // macOS accessibility trust check to avoid crash when global key hook is denied
@@ -16,6 +33,7 @@ extern "C" {
 
 use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::OnceLock;
 use std::time::{Duration, Instant};
@@ -492,8 +510,10 @@ fn kill_process(pid: u32) -> Result<(), String> {
     }
 }
 
+// Runs on every app exit path, so unsaved lifetime counters are written here too
 fn try_stop_server(app: &tauri::AppHandle<Wry>) {
     if let Some(state) = app.try_state::<AppHandle>() {
+        commands::flush_counters(&mut state.app_state.write());
         let mut controller = state.server_controller.lock();
         if controller.is_running() {
             let _ = controller.stop();
@@ -539,6 +559,14 @@ fn minimize_to_tray(window: tauri::Window<Wry>) -> Result<(), String> {
         let overlay_item =
             MenuItem::with_id(app, "open_overlay_tray", "Overlay", true, None::<&str>)
                 .map_err(|e| format!("Failed to create menu item: {}", e))?;
+        let pause_item = MenuItem::with_id(
+            app,
+            "toggle_pause_tray",
+            "Pause/Resume Capture",
+            true,
+            None::<&str>,
+        )
+        .map_err(|e| format!("Failed to create menu item: {}", e))?;
         let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)
             .map_err(|e| format!("Failed to create menu item: {}", e))?;
         let menu = Menu::with_items(
@@ -549,6 +577,7 @@ fn minimize_to_tray(window: tauri::Window<Wry>) -> Result<(), String> {
                 &stop_server_item,
                 &control_item,
                 &overlay_item,
+                &pause_item,
                 &quit_item,
             ],
         )
@@ -590,6 +619,13 @@ fn minimize_to_tray(window: tauri::Window<Wry>) -> Result<(), String> {
                     "open_overlay_tray" => {
                         open_service_url("/overlay");
                     }
+                    "toggle_pause_tray" => {
+                        if let Some(state) = app.try_state::<AppHandle>() {
+                            let mut state_lock = state.app_state.write();
+                            let paused = !state_lock.capture_paused;
+                            commands::set_capture_paused(&mut state_lock, paused);
+                        }
+                    }
                     "quit" => {
                         try_stop_server(app);
                         app.exit(0);
@@ -685,7 +721,37 @@ fn reset_settings() -> Result<(), String> {
     settings::reset_all_settings()
 }
 
+// `KBQV <subcommand> ...`: gets the arguments after the subcommand, returns the written file
+type CliCommand = fn(&[String]) -> Result<PathBuf, String>;
+
 fn main() {
+    // Command-line tools run instead of the app: no window, server, elevation or instance lock
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cli: Option<CliCommand> = match args.first().map(String::as_str) {
+        Some("export-subtitles") => Some(subtitles::run_cli),
+        Some("export-csv") => Some(export::run_cli),
+        _ => None,
+    };
+    if let Some(run_cli) = cli {
+        // Release builds use the GUI subsystem and start without a console;
+        // print to the terminal that launched us (fails harmlessly if there is none)
+        #[cfg(target_os = "windows")]
+        unsafe {
+            use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+            let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+        match run_cli(&args[1..]) {
+            Ok(path) => {
+                println!("{}", path.display());
+                std::process::exit(0);
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+
     #[cfg(target_os = "windows")]
     {
         let disable_auto_elevate = std::env::var("KV_NO_AUTO_ELEVATE").unwrap_or_default();
@@ -823,6 +889,37 @@ fn main() {
     initial_state.app_config.key_style = settings::load_key_style_config();
     println!("Loaded key style config");
 
+    // Load saved profiles from file
+    initial_state.profiles = settings::load_profiles_config();
+    println!(
+        "Loaded {} profile(s)",
+        initial_state.profiles.profiles.len()
+    );
+
+    // Load pause/mask options from file
+    initial_state.privacy = settings::load_privacy_config();
+    println!("Loaded privacy config");
+
+    // Load global hotkey bindings from file
+    initial_state.hotkeys = settings::load_hotkeys_config();
+    println!(
+        "Loaded {} hotkey binding(s)",
+        initial_state.hotkeys.bindings.len()
+    );
+
+    // Load chord display options from file
+    initial_state.display = settings::load_display_config();
+    println!("Loaded display config");
+
+    // Load counter-strafe analyzer options from file
+    initial_state.strafe_config = settings::load_strafe_config();
+    initial_state.strafe = strafe::StrafeAnalyzer::new(&initial_state.strafe_config);
+    println!("Loaded strafe config");
+
+    // Load lifetime press counters from file
+    initial_state.counters = settings::load_lifetime_counters();
+    println!("Loaded lifetime counters");
+
     let app_state = Arc::new(RwLock::new(initial_state));
 
     // Create server controller
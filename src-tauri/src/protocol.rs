@@ -0,0 +1,308 @@
+use crate::history::HistoryEntry;
+use crate::state::{KeyImagesConfig, KeyStyleConfig, OverlayConfig};
+use crate::stats::StatsSnapshot;
+use crate::typed_text::Segment;
+use serde::{Deserialize, Serialize};
+
+// Bump when a message changes shape in a way existing overlays would misread.
+// v1 was the unversioned `hello`/`keys`/`shutdown` set.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Message groups a client can opt into with a `subscribe` message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stream {
+    /// Snapshot of currently held labels (`keys`)
+    Keys,
+    /// Individual `press`/`release` transitions (and `repeat`)
+    Events,
+    /// Periodic `stats` summaries
+    Stats,
+    /// `config_changed` pushes
+    Config,
+    /// `foreground` window changes
+    Foreground,
+    /// Modifier + key shortcuts (`chord`), only sent in chord mode
+    Chords,
+    /// Typed-text lines (`text`), only sent in typed-text mode
+    Text,
+    /// Recent presses that stay listed after release (`history`)
+    History,
+    /// Counter-strafe timings (`strafe`), only sent while the analyzer is enabled
+    Strafe,
+}
+
+impl Stream {
+    pub const ALL: [Stream; 9] = [
+        Stream::Keys,
+        Stream::Events,
+        Stream::Stats,
+        Stream::Config,
+        Stream::Foreground,
+        Stream::Chords,
+        Stream::Text,
+        Stream::History,
+        Stream::Strafe,
+    ];
+
+    // What a client gets without ever sending `subscribe` (matches pre-v2 behaviour)
+    pub fn defaults() -> Vec<Stream> {
+        vec![Stream::Keys, Stream::Events, Stream::Config]
+    }
+}
+
+/// Frame type used for server -> client messages. Payload is JSON either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    #[default]
+    Json,
+    JsonBinary,
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 2] = [Encoding::Json, Encoding::JsonBinary];
+}
+
+/// Every message the server sends. Serialized with a `type` tag, e.g. `{"type":"keys",...}`.
+/// `ts` fields are milliseconds on a monotonic clock that starts with the app (not wall time).
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Hello {
+        protocol: u32,
+        boot_id: u64,
+        /// Sequence number of the last retained bus frame at connect time
+        seq: u64,
+        keys: Vec<String>,
+        /// Capture is paused; `status` messages carry later changes
+        paused: bool,
+        /// Overlays should hide their keys (`toggle_overlay`)
+        hidden: bool,
+        /// Keys come from a recording being replayed, not live input
+        replay: bool,
+        streams: Vec<Stream>,
+        encodings: Vec<Encoding>,
+    },
+    Subscribed {
+        streams: Vec<Stream>,
+        encoding: Encoding,
+    },
+    Keys {
+        keys: Vec<String>,
+    },
+    Press {
+        code: u32,
+        label: String,
+        ts: f64,
+    },
+    Release {
+        code: u32,
+        label: String,
+        ts: f64,
+        held_ms: f64,
+    },
+    /// Auto-repeat of a held key (only with `show_repeats`); `count` repeats since its press.
+    /// Not a press: presses and stats only count real key downs.
+    Repeat {
+        code: u32,
+        label: String,
+        count: u32,
+        ts: f64,
+    },
+    /// Shortcut pressed in chord mode, e.g. `label` "CTRL+SHIFT+P".
+    /// `keys` are the held labels it stands for, as they appear in `keys` messages.
+    Chord {
+        label: String,
+        keys: Vec<String>,
+        ts: f64,
+    },
+    /// Whole typed-text buffer after a press changed it; the last line is being typed.
+    /// `plain` is the same lines as strings with badges in brackets. Overlays clear
+    /// the text after `idle_ms` without a new message (0 = keep it).
+    Text {
+        lines: Vec<Vec<Segment>>,
+        plain: Vec<String>,
+        idle_ms: u64,
+        ts: f64,
+    },
+    /// Recent presses, oldest first, sent whenever the list changes.
+    /// Released entries are dropped `linger_ms` after their release.
+    History {
+        entries: Vec<HistoryEntry>,
+        linger_ms: u64,
+    },
+    /// A completed counter-strafe between two opposing keys:
+    /// `delta_ms` > 0 is the gap after releasing `from`, < 0 the overlap with it
+    Strafe {
+        from: String,
+        to: String,
+        delta_ms: f64,
+        ts: f64,
+    },
+    /// Periodic stats: KPS/APM/peak/per-key counters plus the held key count
+    /// and the active profile's lifetime total
+    Stats {
+        #[serde(flatten)]
+        stats: StatsSnapshot,
+        held: usize,
+        lifetime: u64,
+        uptime_ms: f64,
+    },
+    /// Pushed after a config POST; only the sections that changed are present,
+    /// each in the same shape as its GET endpoint. Not retained: a client that
+    /// resumes or falls behind gets every section instead.
+    ConfigChanged {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        overlay: Option<Box<OverlayConfig>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        key_style: Option<Box<KeyStyleConfig>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        key_images: Option<Box<KeyImagesConfig>>,
+    },
+    Foreground {
+        hwnd: Option<String>,
+        title: Option<String>,
+        process_name: Option<String>,
+        class: Option<String>,
+    },
+    /// Reply to `resume`; the `replayed` missed messages follow right after
+    Resumed {
+        from: u64,
+        replayed: usize,
+    },
+    /// Reply to a `command`; `id` is copied from the request
+    Ack {
+        id: Option<u64>,
+        command: String,
+        ok: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        data: Option<serde_json::Value>,
+    },
+    /// Capture/profile state, pushed whenever either changes
+    Status {
+        paused: bool,
+        /// Presses are still shown while paused, but under the mask character
+        masked: bool,
+        hidden: bool,
+        profile: Option<String>,
+        replay: bool,
+    },
+    /// Missed messages could not be replayed; `keys` is the authoritative held state
+    Resync {
+        keys: Vec<String>,
+    },
+    Error {
+        message: String,
+    },
+    Shutdown,
+}
+
+impl ServerMessage {
+    // Stream a message belongs to; `None` means it is always delivered
+    pub fn stream(&self) -> Option<Stream> {
+        match self {
+            ServerMessage::Keys { .. } => Some(Stream::Keys),
+            ServerMessage::Press { .. }
+            | ServerMessage::Release { .. }
+            | ServerMessage::Repeat { .. } => Some(Stream::Events),
+            ServerMessage::Stats { .. } => Some(Stream::Stats),
+            ServerMessage::ConfigChanged { .. } => Some(Stream::Config),
+            ServerMessage::Foreground { .. } => Some(Stream::Foreground),
+            ServerMessage::Chord { .. } => Some(Stream::Chords),
+            ServerMessage::Text { .. } => Some(Stream::Text),
+            ServerMessage::History { .. } => Some(Stream::History),
+            ServerMessage::Strafe { .. } => Some(Stream::Strafe),
+            ServerMessage::Hello { .. }
+            | ServerMessage::Subscribed { .. }
+            | ServerMessage::Resumed { .. }
+            | ServerMessage::Ack { .. }
+            | ServerMessage::Status { .. }
+            | ServerMessage::Resync { .. }
+            | ServerMessage::Error { .. }
+            | ServerMessage::Shutdown => None,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// A retained bus message as sent on the wire, e.g. `{"type":"press",...,"seq":42}`.
+/// Clients keep the last `seq` they saw and hand it back to resume after a reconnect.
+#[derive(Serialize)]
+pub struct Sequenced<'a> {
+    #[serde(flatten)]
+    pub message: &'a ServerMessage,
+    pub seq: u64,
+}
+
+impl Sequenced<'_> {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Messages accepted from clients
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe {
+        #[serde(default = "Stream::defaults")]
+        streams: Vec<Stream>,
+        #[serde(default)]
+        encoding: Encoding,
+    },
+    /// Replay everything after `last_seq`; `boot_id` is from the `hello` that seq belongs to
+    Resume { boot_id: u64, last_seq: u64 },
+    /// Run a command, e.g. `{"type":"command","id":1,"command":"clear_keys"}`
+    Command {
+        #[serde(default)]
+        id: Option<u64>,
+        #[serde(flatten)]
+        command: Command,
+    },
+}
+
+/// Actions accepted over /ws; each one is answered with an `ack`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    ClearKeys,
+    PauseCapture,
+    ResumeCapture,
+    TogglePause,
+    SwitchProfile {
+        name: String,
+    },
+    /// Step through the saved profiles in list order, wrapping around
+    NextProfile,
+    PreviousProfile,
+    ToggleOverlay,
+    ResetStats,
+    /// `ts` is echoed back untouched so the client can measure the round trip
+    Ping {
+        #[serde(default)]
+        ts: Option<f64>,
+    },
+}
+
+impl Command {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::ClearKeys => "clear_keys",
+            Command::PauseCapture => "pause_capture",
+            Command::ResumeCapture => "resume_capture",
+            Command::TogglePause => "toggle_pause",
+            Command::SwitchProfile { .. } => "switch_profile",
+            Command::NextProfile => "next_profile",
+            Command::PreviousProfile => "previous_profile",
+            Command::ToggleOverlay => "toggle_overlay",
+            Command::ResetStats => "reset_stats",
+            Command::Ping { .. } => "ping",
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use crate::window_info::WindowInfo;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::path::{Path, PathBuf};
+
+// Session recording: every press / release / auto-repeat written as one JSON line to
+// `<config>/recordings/`, for post-stream analysis and editing tutorial videos.
+// A session starts with a header line; past `MAX_PART_BYTES` it continues in a new part
+// file (with its own header), so no single file grows without bound. Old recordings are
+// only removed through the API. Nothing is recorded while capture is paused.
+
+pub const FORMAT_VERSION: u32 = 1;
+// Size at which the current part is closed and the next one opened
+const MAX_PART_BYTES: u64 = 16 * 1024 * 1024;
+const FILE_PREFIX: &str = "rec-";
+const FILE_EXT: &str = "jsonl";
+
+/// Foreground window at the time of an event
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedWindow {
+    pub title: String,
+    pub process: String,
+}
+
+#[derive(Serialize)]
+struct Header<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    version: u32,
+    /// Id of the first part; shared by every part of the session
+    session: &'a str,
+    part: u32,
+    started_unix_ms: u64,
+    profile: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct EventLine<'a> {
+    #[serde(rename = "type")]
+    kind: &'a str,
+    /// Monotonic event clock (same as `ts` on the WS/SSE events)
+    ts: f64,
+    unix_ms: u64,
+    code: u32,
+    label: &'a str,
+    source: &'static str,
+    window: Option<&'a RecordedWindow>,
+}
+
+/// `/api/recordings` status of the session being written
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingStatus {
+    pub recording: bool,
+    pub session: Option<String>,
+    /// File currently written to
+    pub id: Option<String>,
+    pub started_unix_ms: Option<u64>,
+    pub parts: u32,
+    pub events: u64,
+}
+
+/// One recorded session in the recordings directory, with all of its part files
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingFile {
+    /// Session id (file name of the first part without the extension); what the API takes as `id`
+    pub id: String,
+    pub parts: u32,
+    /// Total size of the parts
+    pub size: u64,
+    /// Latest modification of any part
+    pub modified_unix_ms: u64,
+}
+
+// One `rec-*.jsonl` file: `rec-<ms>.jsonl` is part 0, `rec-<ms>.<n>.jsonl` part n
+struct PartFile {
+    session: String,
+    part: u32,
+    path: PathBuf,
+    size: u64,
+    modified_unix_ms: u64,
+}
+
+struct Session {
+    dir: PathBuf,
+    id: String,
+    started_unix_ms: u64,
+    profile: Option<String>,
+    part: u32,
+    part_id: String,
+    file: LineWriter<File>,
+    bytes: u64,
+    events: u64,
+}
+
+fn create_part(dir: &Path, part_id: &str) -> Result<LineWriter<File>, String> {
+    let path = dir.join(format!("{}.{}", part_id, FILE_EXT));
+    let file =
+        File::create(&path).map_err(|e| format!("Failed to create recording file: {}", e))?;
+    Ok(LineWriter::new(file))
+}
+
+impl Session {
+    // Close the current part and continue in `<session>.<part>.jsonl`
+    fn next_part(&mut self, unix_ms: u64) -> Result<(), String> {
+        self.part += 1;
+        self.part_id = format!("{}.{}", self.id, self.part);
+        self.file = create_part(&self.dir, &self.part_id)?;
+        self.bytes = 0;
+        self.write_header(unix_ms)
+    }
+
+    fn write_header(&mut self, unix_ms: u64) -> Result<(), String> {
+        let header = Header {
+            kind: "header",
+            version: FORMAT_VERSION,
+            session: &self.id,
+            part: self.part,
+            started_unix_ms: unix_ms,
+            profile: self.profile.as_deref(),
+        };
+        let line = serde_json::to_string(&header).unwrap_or_default();
+        self.write_line(&line)
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), String> {
+        self.file
+            .write_all(line.as_bytes())
+            .and_then(|_| self.file.write_all(b"\n"))
+            .map_err(|e| format!("Failed to write recording: {}", e))?;
+        self.bytes += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct Recorder {
+    session: Option<Session>,
+    window: Option<RecordedWindow>,
+}
+
+impl Recorder {
+    pub fn is_recording(&self) -> bool {
+        self.session.is_some()
+    }
+
+    // Kept current by the foreground poller while recording
+    pub fn set_window(&mut self, window: Option<&WindowInfo>) {
+        self.window = window.map(|window| RecordedWindow {
+            title: window.title.clone(),
+            process: window.process.clone(),
+        });
+    }
+
+    pub fn start(
+        &mut self,
+        dir: PathBuf,
+        profile: Option<&str>,
+        unix_ms: u64,
+    ) -> Result<(), String> {
+        if self.session.is_some() {
+            return Err("Recording already in progress".to_string());
+        }
+        let id = format!("{}{}", FILE_PREFIX, unix_ms);
+        let file = create_part(&dir, &id)?;
+        let mut session = Session {
+            dir,
+            part_id: id.clone(),
+            id,
+            started_unix_ms: unix_ms,
+            profile: profile.map(str::to_string),
+            part: 0,
+            file,
+            bytes: 0,
+            events: 0,
+        };
+        session.write_header(unix_ms)?;
+        self.session = Some(session);
+        Ok(())
+    }
+
+    // Returns the status of the session that was closed, if any
+    pub fn stop(&mut self) -> Option<RecordingStatus> {
+        let status = self.status();
+        let mut session = self.session.take()?;
+        let _ = session.file.flush();
+        Some(status)
+    }
+
+    // `kind`: "press" | "release" | "repeat"
+    pub fn record(&mut self, kind: &str, ts: f64, unix_ms: u64, code: u32, label: &str) {
+        let Some(session) = self.session.as_mut() else {
+            return;
+        };
+        let line = EventLine {
+            kind,
+            ts,
+            unix_ms,
+            code,
+            label,
+            source: source(label),
+            window: self.window.as_ref(),
+        };
+        let line = serde_json::to_string(&line).unwrap_or_default();
+        let result = if session.bytes + line.len() as u64 > MAX_PART_BYTES {
+            session.next_part(unix_ms)
+        } else {
+            Ok(())
+        };
+        let result = result.and_then(|_| session.write_line(&line));
+        match result {
+            Ok(()) => session.events += 1,
+            Err(error) => {
+                // A failing disk ends the session instead of erroring on every key
+                eprintln!("[Recorder] {}", error);
+                self.session = None;
+            }
+        }
+    }
+
+    pub fn status(&self) -> RecordingStatus {
+        match &self.session {
+            Some(session) => RecordingStatus {
+                recording: true,
+                session: Some(session.id.clone()),
+                id: Some(session.part_id.clone()),
+                started_unix_ms: Some(session.started_unix_ms),
+                parts: session.part + 1,
+                events: session.events,
+            },
+            None => RecordingStatus {
+                recording: false,
+                session: None,
+                id: None,
+                started_unix_ms: None,
+                parts: 0,
+                events: 0,
+            },
+        }
+    }
+
+    // Any part of session `id` is being written
+    fn writing(&self, id: &str) -> bool {
+        self.session
+            .as_ref()
+            .is_some_and(|session| session.id == id)
+    }
+}
+
+// Mouse buttons arrive as LMB/RMB/MMB (MB<n> for extra buttons on Linux)
+fn source(label: &str) -> &'static str {
+    let extra_button = label
+        .strip_prefix("MB")
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+    if matches!(label, "LMB" | "RMB" | "MMB") || extra_button {
+        "mouse"
+    } else {
+        "keyboard"
+    }
+}
+
+fn part_files(dir: &Path) -> Vec<PartFile> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != FILE_EXT {
+                return None;
+            }
+            let stem = path.file_stem()?.to_str()?.to_string();
+            if !stem.starts_with(FILE_PREFIX) {
+                return None;
+            }
+            let (session, part) = match stem.rsplit_once('.') {
+                Some((session, part)) => (session.to_string(), part.parse().ok()?),
+                None => (stem, 0),
+            };
+            let metadata = entry.metadata().ok()?;
+            let modified_unix_ms = metadata
+                .modified()
+                .ok()
+                .and_then(|at| at.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |at| at.as_millis() as u64);
+            Some(PartFile {
+                session,
+                part,
+                size: metadata.len(),
+                modified_unix_ms,
+                path,
+            })
+        })
+        .collect()
+}
+
+// Recorded sessions on disk, newest first
+pub fn list(dir: &Path) -> Vec<RecordingFile> {
+    let mut sessions: Vec<RecordingFile> = Vec::new();
+    for file in part_files(dir) {
+        match sessions
+            .iter_mut()
+            .find(|session| session.id == file.session)
+        {
+            Some(session) => {
+                session.parts += 1;
+                session.size += file.size;
+                session.modified_unix_ms = session.modified_unix_ms.max(file.modified_unix_ms);
+            }
+            None => sessions.push(RecordingFile {
+                id: file.session,
+                parts: 1,
+                size: file.size,
+                modified_unix_ms: file.modified_unix_ms,
+            }),
+        }
+    }
+    sessions.sort_by(|a, b| {
+        b.modified_unix_ms
+            .cmp(&a.modified_unix_ms)
+            .then_with(|| b.id.cmp(&a.id))
+    });
+    sessions
+}
+
+// Part files of session `id` in part order, or None if there is no such session.
+// Only ids found on disk resolve, so a crafted id can't reach outside the directory.
+pub fn session_paths(dir: &Path, id: &str) -> Option<Vec<PathBuf>> {
+    let mut parts: Vec<PartFile> = part_files(dir)
+        .into_iter()
+        .filter(|file| file.session == id)
+        .collect();
+    parts.sort_by_key(|file| file.part);
+    (!parts.is_empty()).then(|| parts.into_iter().map(|file| file.path).collect())
+}
+
+// Removes every part of session `id`; returns whether there was anything to remove.
+// A session still being written can't be deleted.
+pub fn delete(recorder: &Recorder, dir: &Path, id: &str) -> Result<bool, String> {
+    if recorder.writing(id) {
+        return Err("Recording in progress; stop it first".to_string());
+    }
+    let Some(paths) = session_paths(dir, id) else {
+        return Ok(false);
+    };
+    for path in paths {
+        std::fs::remove_file(&path).map_err(|e| format!("Failed to delete recording: {}", e))?;
+    }
+    Ok(true)
+}
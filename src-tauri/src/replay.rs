@@ -0,0 +1,194 @@
+use crate::cli::read_lines;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+// Plays a session recording (`recorder.rs`) back into `AppState`, so overlays and the
+// /ws stream see it as live input: for designing overlay styles or re-rendering key
+// displays for edited VODs. The server's replay task takes due events every tick.
+// While a replay runs, live input is ignored and nothing is persisted (counters,
+// press log, recordings).
+
+pub const MIN_SPEED: f64 = 0.1;
+pub const MAX_SPEED: f64 = 16.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventKind {
+    Press,
+    Release,
+    Repeat,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayEvent {
+    /// Position in the replay (ms from the first event)
+    pub at_ms: f64,
+    pub kind: EventKind,
+    pub code: u32,
+    pub label: String,
+}
+
+// One line of a recording; header lines and unknown types are skipped
+#[derive(Deserialize)]
+struct Line {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    ts: f64,
+    #[serde(default)]
+    code: u32,
+    #[serde(default)]
+    label: String,
+}
+
+/// `/api/replay` response while a replay is loaded
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayStatus {
+    pub id: String,
+    pub paused: bool,
+    pub speed: f64,
+    pub position_ms: f64,
+    pub duration_ms: f64,
+    pub events: usize,
+    /// Events already played
+    pub played: usize,
+}
+
+// Files of a recording: every part of the session, in order
+pub fn session_paths(dir: &Path, id: &str) -> Result<Vec<PathBuf>, String> {
+    crate::recorder::session_paths(dir, id).ok_or_else(|| "Recording not found".to_string())
+}
+
+pub fn load(dir: &Path, id: &str) -> Result<Vec<ReplayEvent>, String> {
+    load_paths(&session_paths(dir, id)?)
+}
+
+pub fn load_paths(paths: &[PathBuf]) -> Result<Vec<ReplayEvent>, String> {
+    let lines: Vec<Line> = read_lines(paths)?;
+    let events: Vec<(f64, EventKind, u32, String)> = lines
+        .into_iter()
+        .filter_map(|line| {
+            let kind = match line.kind.as_str() {
+                "press" => EventKind::Press,
+                "release" => EventKind::Release,
+                "repeat" => EventKind::Repeat,
+                _ => return None,
+            };
+            Some((line.ts, kind, line.code, line.label))
+        })
+        .collect();
+    let Some(start) = events.first().map(|event| event.0) else {
+        return Err("Recording has no events".to_string());
+    };
+    Ok(events
+        .into_iter()
+        .map(|(ts, kind, code, label)| ReplayEvent {
+            at_ms: (ts - start).max(0.0),
+            kind,
+            code,
+            label,
+        })
+        .collect())
+}
+
+pub struct Replay {
+    id: String,
+    events: Vec<ReplayEvent>,
+    // Next event to play
+    cursor: usize,
+    speed: f64,
+    paused: bool,
+    // Replay position at `anchor`; advances with wall time × speed unless paused
+    position_ms: f64,
+    anchor: Instant,
+}
+
+impl Replay {
+    pub fn new(id: String, events: Vec<ReplayEvent>, speed: f64, now: Instant) -> Self {
+        Self {
+            id,
+            events,
+            cursor: 0,
+            speed,
+            paused: false,
+            position_ms: 0.0,
+            anchor: now,
+        }
+    }
+
+    fn duration_ms(&self) -> f64 {
+        self.events.last().map_or(0.0, |event| event.at_ms)
+    }
+
+    pub fn position(&self, now: Instant) -> f64 {
+        let position = if self.paused {
+            self.position_ms
+        } else {
+            let elapsed = now.saturating_duration_since(self.anchor).as_secs_f64() * 1000.0;
+            self.position_ms + elapsed * self.speed
+        };
+        position.min(self.duration_ms())
+    }
+
+    // Events whose time has come, in order
+    pub fn due(&mut self, now: Instant) -> Vec<ReplayEvent> {
+        let position = self.position(now);
+        let start = self.cursor;
+        while self
+            .events
+            .get(self.cursor)
+            .is_some_and(|event| event.at_ms <= position)
+        {
+            self.cursor += 1;
+        }
+        self.events[start..self.cursor].to_vec()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.cursor == self.events.len()
+    }
+
+    pub fn set_paused(&mut self, paused: bool, now: Instant) {
+        self.position_ms = self.position(now);
+        self.anchor = now;
+        self.paused = paused;
+    }
+
+    pub fn set_speed(&mut self, speed: f64, now: Instant) {
+        self.position_ms = self.position(now);
+        self.anchor = now;
+        self.speed = speed;
+    }
+
+    // Jump to `position_ms`; returns the presses of the keys held at that point, in
+    // press order, for the caller to put back on screen
+    pub fn seek(&mut self, position_ms: f64, now: Instant) -> Vec<ReplayEvent> {
+        let position_ms = position_ms.clamp(0.0, self.duration_ms());
+        self.position_ms = position_ms;
+        self.anchor = now;
+        self.cursor = self
+            .events
+            .partition_point(|event| event.at_ms < position_ms);
+        let mut held: Vec<&ReplayEvent> = Vec::new();
+        for event in &self.events[..self.cursor] {
+            match event.kind {
+                EventKind::Press => held.push(event),
+                EventKind::Release => held.retain(|pressed| pressed.code != event.code),
+                EventKind::Repeat => {}
+            }
+        }
+        held.into_iter().cloned().collect()
+    }
+
+    pub fn status(&self, now: Instant) -> ReplayStatus {
+        ReplayStatus {
+            id: self.id.clone(),
+            paused: self.paused,
+            speed: self.speed,
+            position_ms: self.position(now),
+            duration_ms: self.duration_ms(),
+            events: self.events.len(),
+            played: self.cursor,
+        }
+    }
+}
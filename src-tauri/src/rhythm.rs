@@ -0,0 +1,114 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+// Press timing analysis for rhythm-game practice (osu!, DJMAX), fed from `StatsEngine`.
+// Only intervals inside one continuous run count: a pause longer than `MAX_INTERVAL`
+// starts a new run, so idle time never reads as a slow, unstable stream.
+
+// Longest gap still treated as part of the same run
+const MAX_INTERVAL: Duration = Duration::from_secs(1);
+// Rolling window: most recent intervals kept (overall and per key)
+const WINDOW: usize = 32;
+
+/// Timing of the current run, overall (any key to any key) or for one key
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Timing {
+    /// Intervals in the window
+    pub samples: usize,
+    /// Mean inter-press interval
+    pub interval_ms: Option<f64>,
+    /// Mean absolute difference between consecutive intervals
+    pub jitter_ms: Option<f64>,
+    /// osu!-style unstable rate: 10 × standard deviation of the intervals
+    pub unstable_rate: Option<f64>,
+}
+
+/// Rhythm section of `/api/stats` and the `stats` stream
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RhythmSnapshot {
+    #[serde(flatten)]
+    pub overall: Timing,
+    /// Tempo if every press is a 1/4 note (osu! stream convention: 15000 / interval)
+    pub bpm: Option<f64>,
+    pub per_key: BTreeMap<String, Timing>,
+}
+
+#[derive(Default)]
+struct Intervals {
+    last: Option<Instant>,
+    window: VecDeque<f64>,
+}
+
+impl Intervals {
+    fn push(&mut self, now: Instant) {
+        if let Some(last) = self.last {
+            let interval = now.saturating_duration_since(last);
+            if interval > MAX_INTERVAL {
+                self.window.clear();
+            } else {
+                if self.window.len() == WINDOW {
+                    self.window.pop_front();
+                }
+                self.window.push_back(interval.as_secs_f64() * 1000.0);
+            }
+        }
+        self.last = Some(now);
+    }
+
+    fn timing(&self) -> Timing {
+        let samples = self.window.len();
+        if samples == 0 {
+            return Timing::default();
+        }
+        let n = samples as f64;
+        let mean = self.window.iter().sum::<f64>() / n;
+        let variance = self.window.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+        let jitter = (samples > 1).then(|| {
+            let diffs = self.window.iter().zip(self.window.iter().skip(1));
+            diffs.map(|(a, b)| (b - a).abs()).sum::<f64>() / (n - 1.0)
+        });
+        Timing {
+            samples,
+            interval_ms: Some(mean),
+            jitter_ms: jitter,
+            // A single interval has no spread to speak of
+            unstable_rate: (samples > 1).then(|| variance.sqrt() * 10.0),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RhythmAnalyzer {
+    overall: Intervals,
+    per_key: HashMap<String, Intervals>,
+}
+
+impl RhythmAnalyzer {
+    pub fn record_press(&mut self, label: &str, now: Instant) {
+        // A new run starts for every key, not just the ones pressed again
+        let run_over = self
+            .overall
+            .last
+            .is_some_and(|last| now.saturating_duration_since(last) > MAX_INTERVAL);
+        if run_over {
+            self.per_key.clear();
+        }
+        self.overall.push(now);
+        self.per_key.entry(label.to_string()).or_default().push(now);
+    }
+
+    pub fn snapshot(&self) -> RhythmSnapshot {
+        let overall = self.overall.timing();
+        RhythmSnapshot {
+            bpm: overall.interval_ms.map(|interval| 15000.0 / interval),
+            overall,
+            per_key: self
+                .per_key
+                .iter()
+                .filter(|(_, intervals)| !intervals.window.is_empty())
+                .map(|(label, intervals)| (label.clone(), intervals.timing()))
+                .collect(),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::bus::Frame;
+use crate::protocol::{ClientMessage, Encoding, ServerMessage, Stream, PROTOCOL_VERSION};
 use crate::state::{AppState, KeyImagesConfig, KeyStyleConfig, TargetConfig};
 use crate::window_info;
 use axum::{
@@ -6,18 +8,22 @@ use axum::{
         ws::{Message, WebSocket},
         Query, State as AxumState, WebSocketUpgrade,
     },
-    http::{header, StatusCode},
-    response::{IntoResponse, Json, Response},
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
     routing::get,
     Router,
 };
 use parking_lot::RwLock;
 use serde_json::json;
+use std::collections::{HashSet, VecDeque};
+use std::convert::Infallible;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::watch;
-use tokio::time::sleep;
+use tokio::sync::broadcast;
 use tower_http::cors::{Any, CorsLayer};
 
 // Embed UI files at compile time
@@ -37,6 +43,18 @@ const JS_CLOUD_AUTH: &str = include_str!("../../ui/js/cloud-auth.js");
 
 type SharedState = Arc<RwLock<AppState>>;
 
+// How often `stats` is published / the foreground window is polled
+const STATS_INTERVAL: Duration = Duration::from_millis(250);
+const FOREGROUND_POLL_INTERVAL: Duration = Duration::from_millis(500);
+// Granularity of history linger expiry
+const HISTORY_EXPIRY_INTERVAL: Duration = Duration::from_millis(100);
+// Granularity of deferred key removal (minimum display time / release grace)
+const LINGER_INTERVAL: Duration = Duration::from_millis(10);
+// Granularity of replay playback
+const REPLAY_INTERVAL: Duration = Duration::from_millis(5);
+// How often changed lifetime counters are written to counters.json (also flushed on exit)
+const COUNTERS_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
 // Server controller for start/stop
 pub struct ServerController {
     runtime: tokio::runtime::Runtime,
@@ -92,17 +110,19 @@ impl ServerController {
 
         let handle = self.runtime.spawn(async move {
             // Mark server alive in shared state and bump cache buster
-            {
+            let boot_id = {
                 let mut s = state.write();
                 s.server_alive = true;
                 s.bump_cache_buster();
-            }
-            // Initialize watch channel for immediate key updates
-            {
-                let (tx, _rx) = watch::channel::<Vec<String>>(Vec::new());
-                let mut s = state.write();
-                s.set_event_tx(tx);
-            }
+                s.cache_buster
+            };
+            // Periodic producers publish once onto the bus for every client
+            tokio::spawn(publish_stats(state.clone(), boot_id));
+            tokio::spawn(publish_foreground(state.clone(), boot_id));
+            tokio::spawn(expire_history(state.clone(), boot_id));
+            tokio::spawn(flush_lingering(state.clone(), boot_id));
+            tokio::spawn(flush_counters(state.clone(), boot_id));
+            tokio::spawn(drive_replay(state.clone(), boot_id));
             let app = create_router(state.clone());
             let addr = SocketAddr::from(([127, 0, 0, 1], port));
 
@@ -146,14 +166,12 @@ impl ServerController {
             return Err("서버가 실행 중이 아닙니다".to_string());
         }
 
-        // Flip server_alive and poke watchers to wake waiting tasks
+        // Flip server_alive and send the final frame so every client closes
         if let Some(st) = &self.state_ref {
             let mut s = st.write();
             s.server_alive = false;
             s.clear_keys();
-            if let Some(tx) = &s.event_tx {
-                let _ = tx.send(Vec::new());
-            }
+            s.bus.publish_last(ServerMessage::Shutdown.to_json());
         }
 
         // Send shutdown signal to gracefully stop the server
@@ -199,6 +217,7 @@ fn create_router(state: SharedState) -> Router {
         .route("/js/chip-preview.js", get(get_js_chip_preview))
         .route("/js/cloud-auth.js", get(get_js_cloud_auth))
         .route("/ws", get(websocket_handler))
+        .route("/events", get(sse_handler))
         .route("/api/windows", get(api_windows))
         .route("/api/foreground", get(api_foreground))
         .route("/api/target", get(api_get_target))
@@ -216,6 +235,74 @@ fn create_router(state: SharedState) -> Router {
         .route("/api/key-images", axum::routing::post(api_set_key_images))
         .route("/api/key-style", get(api_get_key_style))
         .route("/api/key-style", axum::routing::post(api_set_key_style))
+        .route("/api/profiles", get(api_get_profiles))
+        .route("/api/profiles", axum::routing::post(api_save_profile))
+        .route(
+            "/api/profiles/activate",
+            axum::routing::post(api_activate_profile),
+        )
+        .route(
+            "/api/profiles/delete",
+            axum::routing::post(api_delete_profile),
+        )
+        .route("/api/stats", get(api_get_stats))
+        .route("/api/stats/reset", axum::routing::post(api_reset_stats))
+        .route("/api/counters", get(api_get_counters))
+        .route(
+            "/api/counters/reset",
+            axum::routing::post(api_reset_counters),
+        )
+        .route("/api/counters/export", get(api_export_counters))
+        .route("/api/strafe", get(api_get_strafe))
+        .route("/api/strafe/reset", axum::routing::post(api_reset_strafe))
+        .route("/api/strafe-config", get(api_get_strafe_config))
+        .route(
+            "/api/strafe-config",
+            axum::routing::post(api_set_strafe_config),
+        )
+        .route("/api/diagnostics", get(api_get_diagnostics))
+        .route("/api/diagnostics", axum::routing::post(api_set_diagnostics))
+        .route("/api/diagnostics/export", get(api_export_diagnostics))
+        .route("/api/heatmap.svg", get(api_heatmap_svg))
+        .route("/api/recordings", get(api_get_recordings))
+        .route(
+            "/api/recordings/start",
+            axum::routing::post(api_start_recording),
+        )
+        .route(
+            "/api/recordings/stop",
+            axum::routing::post(api_stop_recording),
+        )
+        .route(
+            "/api/recordings/delete",
+            axum::routing::post(api_delete_recording),
+        )
+        .route("/api/recordings/file", get(api_get_recording_file))
+        .route("/api/export", get(api_export_csv))
+        .route(
+            "/api/recordings/subtitles",
+            get(api_get_recording_subtitles),
+        )
+        .route("/api/replay", get(api_get_replay))
+        .route("/api/replay/start", axum::routing::post(api_start_replay))
+        .route("/api/replay/pause", axum::routing::post(api_pause_replay))
+        .route("/api/replay/seek", axum::routing::post(api_seek_replay))
+        .route(
+            "/api/replay/speed",
+            axum::routing::post(api_set_replay_speed),
+        )
+        .route("/api/replay/stop", axum::routing::post(api_stop_replay))
+        .route("/api/pause", get(api_get_pause))
+        .route("/api/pause", axum::routing::post(api_set_pause))
+        .route("/api/privacy", get(api_get_privacy))
+        .route("/api/privacy", axum::routing::post(api_set_privacy))
+        .route("/api/hotkeys", get(api_get_hotkeys))
+        .route("/api/hotkeys", axum::routing::post(api_set_hotkeys))
+        .route("/api/display-config", get(api_get_display_config))
+        .route(
+            "/api/display-config",
+            axum::routing::post(api_set_display_config),
+        )
         .route("/obs-local-file", get(get_obs_local_file))
         .layer(cors)
         .with_state(state)
@@ -241,6 +328,10 @@ async fn root_redirect() -> impl IntoResponse {
 struct ObsLocalFileQuery {
     #[serde(rename = "hideKeyText")]
     hide_key_text: Option<String>,
+    // Queue contents baked into the file ("held" | "history"), like /overlay?source=
+    source: Option<String>,
+    // Counter-strafe readout baked in, like /overlay?strafe=1
+    strafe: Option<String>,
 }
 
 // Generate and serve the OBS local-file HTML.
@@ -269,6 +360,11 @@ async fn get_obs_local_file(
         .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
         .unwrap_or_else(|| json!({}))
         .to_string();
+    let key_source = match query.source.as_deref() {
+        Some("history") => "history",
+        _ => "held",
+    };
+    let show_strafe = query.strafe.as_deref() == Some("1");
     let base = format!("http://127.0.0.1:{}", port);
     let ws_url = format!("ws://127.0.0.1:{}/ws", port);
 
@@ -329,6 +425,14 @@ async fn get_obs_local_file(
             "let keyStyleConfig = null;",
             &format!("let keyStyleConfig = {};", key_style_snapshot),
         )
+        .replace(
+            "const KEY_SOURCE = new URLSearchParams(location.search).get('source') || 'held';",
+            &format!("const KEY_SOURCE = {};", json!(key_source)),
+        )
+        .replace(
+            "const SHOW_STRAFE = new URLSearchParams(location.search).get('strafe') === '1';",
+            &format!("const SHOW_STRAFE = {};", show_strafe),
+        )
         .replace(
             "const BAKED_HIDE_KEY_TEXT = {};",
             &format!("const BAKED_HIDE_KEY_TEXT = {};", hide_key_text_snapshot),
@@ -337,14 +441,20 @@ async fn get_obs_local_file(
             "Promise.all([initConfig(), loadKeyImagesConfig(), loadKeyStyleConfig()]).then(()=> setupConfigPanel()); connect();",
             "if (overlayCfg) applyOverlayConfig(overlayCfg); Promise.all([initConfig(), loadKeyImagesConfig(), loadKeyStyleConfig()]).then(()=> setupConfigPanel()); connect();",
         )
-        // 7. Make WS URL absolute
-        .replace(
-            "const url = (location.protocol === 'https:' ? 'wss://' : 'ws://') + location.host + '/ws';",
-            &format!("const url = '{}'; // [local file] absolute URL", ws_url),
-        )
         // 8. Make all fetch() calls use absolute URL
         .replace("fetch('/api/", &format!("fetch('{}/api/", base));
 
+    // 7. Make WS URL absolute (the resume query is appended to it on reconnect)
+    let html = match replace_required(
+        html,
+        "let url = (location.protocol === 'https:' ? 'wss://' : 'ws://') + location.host + '/ws';",
+        &format!("let url = '{}'; // [local file] absolute URL", ws_url),
+        "WebSocket URL",
+    ) {
+        Ok(html) => html,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err).into_response(),
+    };
+
     Response::builder()
         .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
         .header(
@@ -511,69 +621,669 @@ async fn get_favicon() -> impl IntoResponse {
         .unwrap()
 }
 
+#[derive(serde::Deserialize, Default)]
+struct WsQuery {
+    // Resume point from a previous connection; both must be set
+    boot_id: Option<u64>,
+    last_seq: Option<u64>,
+}
+
 async fn websocket_handler(
     ws: WebSocketUpgrade,
+    Query(query): Query<WsQuery>,
     AxumState(state): AxumState<SharedState>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+    let resume_from = query.boot_id.zip(query.last_seq);
+    ws.on_upgrade(move |socket| handle_socket(socket, state, resume_from))
 }
 
-async fn handle_socket(mut socket: WebSocket, state: SharedState) {
-    // Send hello with boot_id so the client can detect stale cached pages
-    let (keys, boot_id) = {
-        let state_lock = state.read();
-        (state_lock.get_keys(), state_lock.cache_buster)
-    };
-    let initial_msg = json!({ "type": "hello", "boot_id": boot_id, "keys": keys });
-    if socket
-        .send(Message::Text(initial_msg.to_string()))
-        .await
-        .is_err()
-    {
-        return;
+// Per-connection delivery settings, changed by `subscribe`
+struct Subscription {
+    streams: HashSet<Stream>,
+    encoding: Encoding,
+}
+
+impl Subscription {
+    fn wants(&self, stream: Option<Stream>) -> bool {
+        stream.is_none_or(|stream| self.streams.contains(&stream))
     }
+}
 
-    // Keep connection alive and send updates but exit if server is stopping
-    // Subscribe to immediate updates
-    let mut rx_opt: Option<watch::Receiver<Vec<String>>> = {
-        let s = state.read();
-        s.event_tx.as_ref().map(|tx| tx.subscribe())
+async fn send_json(socket: &mut WebSocket, sub: &Subscription, payload: String) -> bool {
+    let frame = match sub.encoding {
+        Encoding::Json => Message::Text(payload),
+        Encoding::JsonBinary => Message::Binary(payload.into_bytes()),
     };
+    socket.send(frame).await.is_ok()
+}
 
-    let mut last_sent = String::new();
+// Per-connection messages (hello, replies, resync); returns false once the socket is gone
+async fn send_message(socket: &mut WebSocket, sub: &Subscription, message: &ServerMessage) -> bool {
+    if !sub.wants(message.stream()) {
+        return true;
+    }
+    send_json(socket, sub, message.to_json()).await
+}
+
+async fn send_messages(
+    socket: &mut WebSocket,
+    sub: &Subscription,
+    messages: &[ServerMessage],
+) -> bool {
+    for message in messages {
+        if !send_message(socket, sub, message).await {
+            return false;
+        }
+    }
+    true
+}
+
+// Bus frames are already serialized; only the copy into the socket frame is per client
+async fn send_frame(socket: &mut WebSocket, sub: &Subscription, frame: &Frame) -> bool {
+    if !sub.wants(frame.stream) {
+        return true;
+    }
+    send_json(socket, sub, frame.json.to_string()).await
+}
+
+// Sent first on every connection so the client can detect stale cached pages
+// `seq` is where the frames that follow continue from
+fn hello_message(s: &AppState, seq: u64, encodings: Vec<Encoding>) -> ServerMessage {
+    ServerMessage::Hello {
+        protocol: PROTOCOL_VERSION,
+        boot_id: s.cache_buster,
+        seq,
+        keys: s.get_keys(),
+        paused: s.capture_paused,
+        hidden: s.overlay_hidden,
+        replay: s.replay.is_some(),
+        streams: Stream::ALL.to_vec(),
+        encodings,
+    }
+}
+
+fn stats_message(s: &AppState) -> ServerMessage {
+    let now = std::time::Instant::now();
+    ServerMessage::Stats {
+        stats: s.stats.snapshot(now),
+        held: s.pressed_at.len(),
+        lifetime: s
+            .counters
+            .get(s.profiles.active.as_deref())
+            .map_or(0, |counter| counter.total),
+        uptime_ms: s.monotonic_ms(now),
+    }
+}
+
+fn foreground_message(window: Option<window_info::WindowInfo>) -> ServerMessage {
+    match window {
+        Some(window) => ServerMessage::Foreground {
+            hwnd: Some(window.hwnd),
+            title: Some(window.title),
+            process_name: Some(window.process),
+            class: Some(window.class),
+        },
+        None => ServerMessage::Foreground {
+            hwnd: None,
+            title: None,
+            process_name: None,
+            class: None,
+        },
+    }
+}
+
+// Publishes `stats` once per interval for all clients; exits when this server instance stops
+async fn publish_stats(state: SharedState, boot_id: u64) {
+    let mut tick = tokio::time::interval(STATS_INTERVAL);
+    loop {
+        tick.tick().await;
+        let mut s = state.write();
+        if !s.server_alive || s.cache_buster != boot_id {
+            break;
+        }
+        if s.bus.receiver_count() == 0 {
+            continue;
+        }
+        let stats = stats_message(&s);
+        s.bus.publish_transient(stats.stream(), stats.to_json());
+    }
+}
+
+// Saves lifetime counters that changed; exits with this server instance
+async fn flush_counters(state: SharedState, boot_id: u64) {
+    let mut tick = tokio::time::interval(COUNTERS_FLUSH_INTERVAL);
     loop {
-        // Prefer event-driven; fall back to small sleep if no channel yet
-        if let Some(rx) = rx_opt.as_mut() {
-            let _ = rx.changed().await; // wake on change
-        } else {
-            sleep(Duration::from_millis(15)).await;
+        tick.tick().await;
+        let mut s = state.write();
+        if !s.server_alive || s.cache_buster != boot_id {
+            break;
         }
+        crate::commands::flush_counters(&mut s);
+    }
+}
 
-        let (alive, keys) = {
+// Feeds due replay events into the state and ends the replay after its last event;
+// exits with this server instance
+async fn drive_replay(state: SharedState, boot_id: u64) {
+    let mut tick = tokio::time::interval(REPLAY_INTERVAL);
+    loop {
+        tick.tick().await;
+        // Most of the time nothing is playing; don't take the write lock (and stall
+        // key input) every 5ms just to find that out
+        {
             let s = state.read();
-            (s.server_alive, s.get_keys())
+            if !s.server_alive || s.cache_buster != boot_id {
+                break;
+            }
+            if s.replay.is_none() {
+                continue;
+            }
+        }
+        let mut s = state.write();
+        let Some(replay) = s.replay.as_mut() else {
+            continue;
         };
+        let due = replay.due(std::time::Instant::now());
+        let finished = replay.finished();
+        for event in due {
+            s.apply_replay(event);
+        }
+        if finished {
+            crate::commands::stop_replay(&mut s);
+        }
+    }
+}
 
-        if !alive {
-            // Inform client and close
-            let _ = socket
-                .send(Message::Text(json!({"type":"shutdown"}).to_string()))
-                .await;
-            let _ = socket.send(Message::Close(None)).await;
+// Removes lingering history entries once their time is up; exits with this server instance
+async fn expire_history(state: SharedState, boot_id: u64) {
+    let mut tick = tokio::time::interval(HISTORY_EXPIRY_INTERVAL);
+    loop {
+        tick.tick().await;
+        let mut s = state.write();
+        if !s.server_alive || s.cache_buster != boot_id {
             break;
         }
+        s.expire_history(std::time::Instant::now());
+    }
+}
+
+// Takes released keys off screen once their minimum display time / grace is over
+async fn flush_lingering(state: SharedState, boot_id: u64) {
+    let mut tick = tokio::time::interval(LINGER_INTERVAL);
+    tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tick.tick().await;
+        {
+            let s = state.read();
+            if !s.server_alive || s.cache_buster != boot_id {
+                break;
+            }
+            if s.lingering.is_empty() {
+                continue;
+            }
+        }
+        state.write().flush_lingering(std::time::Instant::now());
+    }
+}
 
-        let msg_str = json!({ "keys": keys }).to_string();
+// Polls the foreground window and publishes only when it changes
+async fn publish_foreground(state: SharedState, boot_id: u64) {
+    let mut tick = tokio::time::interval(FOREGROUND_POLL_INTERVAL);
+    let mut last_sent = String::new();
+    loop {
+        tick.tick().await;
+        {
+            let s = state.read();
+            if !s.server_alive || s.cache_buster != boot_id {
+                break;
+            }
+            // A recording also needs the window, subscribers or not
+            if s.bus.receiver_count() == 0 && !s.recorder.is_recording() {
+                continue;
+            }
+        }
+        let window = tokio::task::spawn_blocking(window_info::get_foreground_window)
+            .await
+            .ok()
+            .flatten();
+        let mut s = state.write();
+        s.recorder.set_window(window.as_ref());
+        let message = foreground_message(window);
+        let msg_str = message.to_json();
         if msg_str == last_sent {
             continue;
         }
         last_sent = msg_str.clone();
-        if socket.send(Message::Text(msg_str)).await.is_err() {
+        let frame = s.bus.publish_transient(message.stream(), msg_str);
+        s.last_foreground = Some(frame);
+    }
+}
+
+// What a client that fell behind the bus gets instead of the frames it missed.
+// `config_changed` (key images make it too large to keep) and `history` (a full list on
+// every press) are transient, so every catch-up also sends `catch_up_snapshots`.
+enum CatchUp {
+    Replay(Vec<Arc<Frame>>),
+    // The missed frames already left the history; `keys` is the state as of `seq`
+    Resync(u64, ServerMessage),
+}
+
+// Current state of the streams whose updates aren't kept in the bus history
+fn catch_up_snapshots(s: &AppState) -> [ServerMessage; 2] {
+    [s.config_message(), s.history_message()]
+}
+
+fn catch_up(s: &AppState, last_seq: u64) -> CatchUp {
+    match s.bus.since(last_seq) {
+        Some(frames) => CatchUp::Replay(frames),
+        None => CatchUp::Resync(s.bus.seq(), ServerMessage::Resync { keys: s.get_keys() }),
+    }
+}
+
+// A command that fails to parse still gets its `ack` so the sender isn't left waiting
+fn invalid_message_reply(text: &str, err: serde_json::Error) -> ServerMessage {
+    let message = format!("Invalid message: {}", err);
+    let raw: serde_json::Value = serde_json::from_str(text).unwrap_or_default();
+    if raw.get("type").and_then(|t| t.as_str()) != Some("command") {
+        return ServerMessage::Error { message };
+    }
+    ServerMessage::Ack {
+        id: raw.get("id").and_then(|id| id.as_u64()),
+        command: raw
+            .get("command")
+            .and_then(|c| c.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        ok: false,
+        error: Some(message),
+        data: None,
+    }
+}
+
+// Sequence numbers only mean something within the server run that issued them
+fn resume(s: &AppState, boot_id: u64, last_seq: u64) -> CatchUp {
+    if boot_id != s.cache_buster {
+        return CatchUp::Resync(s.bus.seq(), ServerMessage::Resync { keys: s.get_keys() });
+    }
+    catch_up(s, last_seq)
+}
+
+async fn handle_socket(mut socket: WebSocket, state: SharedState, resume_from: Option<(u64, u64)>) {
+    let mut sub = Subscription {
+        streams: Stream::defaults().into_iter().collect(),
+        encoding: Encoding::default(),
+    };
+
+    // Subscribe together with building hello so no frame falls between the two
+    let (hello, mut rx, mut last_seq, caught_up, snapshots) = {
+        let s = state.read();
+        let caught_up = resume_from.map(|(boot_id, from)| (from, resume(&s, boot_id, from)));
+        let seq = match &caught_up {
+            Some((from, CatchUp::Replay(_))) => *from,
+            _ => s.bus.seq(),
+        };
+        (
+            hello_message(&s, seq, Encoding::ALL.to_vec()),
+            s.bus.subscribe(),
+            seq,
+            caught_up.map(|(_, caught_up)| caught_up),
+            resume_from.map(|_| catch_up_snapshots(&s)),
+        )
+    };
+    if !send_message(&mut socket, &sub, &hello).await {
+        return;
+    }
+    if let Some(snapshots) = snapshots {
+        if !send_messages(&mut socket, &sub, &snapshots).await {
+            return;
+        }
+    }
+
+    // Events missed while disconnected go out before anything live
+    let mut backlog = match caught_up {
+        Some(CatchUp::Replay(frames)) => frames,
+        Some(CatchUp::Resync(_, resync)) => {
+            if !send_message(&mut socket, &sub, &resync).await {
+                return;
+            }
+            Vec::new()
+        }
+        None => Vec::new(),
+    };
+
+    loop {
+        if !backlog.is_empty() {
+            let frames = std::mem::take(&mut backlog);
+            if !deliver_frames(&mut socket, &sub, frames, &mut last_seq).await {
+                break;
+            }
+            continue;
+        }
+
+        let frames = tokio::select! {
+            received = rx.recv() => match received {
+                Ok(frame) => vec![frame],
+                // Slow client: replay what it missed from the history, or resync if that's gone too
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    let (caught_up, snapshots) = {
+                        let s = state.read();
+                        (catch_up(&s, last_seq), catch_up_snapshots(&s))
+                    };
+                    if !send_messages(&mut socket, &sub, &snapshots).await {
+                        break;
+                    }
+                    match caught_up {
+                        CatchUp::Replay(frames) => frames,
+                        CatchUp::Resync(seq, resync) => {
+                            last_seq = seq;
+                            if !send_message(&mut socket, &sub, &resync).await {
+                                break;
+                            }
+                            continue;
+                        }
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => {
+                let text = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Binary(bytes))) => String::from_utf8_lossy(&bytes).into_owned(),
+                    Some(Ok(_)) => continue, // ping/pong are answered by axum
+                    Some(Err(_)) | None => break,
+                };
+                let (reply, resend_state) = match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(ClientMessage::Subscribe { streams, encoding }) => {
+                        sub.streams = streams.iter().copied().collect();
+                        sub.encoding = encoding;
+                        (ServerMessage::Subscribed { streams, encoding }, true)
+                    }
+                    Ok(ClientMessage::Resume { boot_id, last_seq: from }) => {
+                        let (caught_up, snapshots) = {
+                            let s = state.read();
+                            (resume(&s, boot_id, from), catch_up_snapshots(&s))
+                        };
+                        if !send_messages(&mut socket, &sub, &snapshots).await {
+                            break;
+                        }
+                        match caught_up {
+                            CatchUp::Replay(frames) => {
+                                last_seq = from;
+                                let resumed = ServerMessage::Resumed {
+                                    from,
+                                    replayed: frames.len(),
+                                };
+                                if !send_message(&mut socket, &sub, &resumed).await {
+                                    break;
+                                }
+                                backlog = frames;
+                            }
+                            CatchUp::Resync(seq, resync) => {
+                                last_seq = seq;
+                                if !send_message(&mut socket, &sub, &resync).await {
+                                    break;
+                                }
+                            }
+                        }
+                        continue;
+                    }
+                    Ok(ClientMessage::Command { id, command }) => {
+                        let result = crate::commands::execute(&mut state.write(), &command);
+                        let (data, error) = match result {
+                            Ok(data) => (data, None),
+                            Err(error) => (None, Some(error)),
+                        };
+                        let ack = ServerMessage::Ack {
+                            id,
+                            command: command.name().to_string(),
+                            ok: error.is_none(),
+                            error,
+                            data,
+                        };
+                        (ack, false)
+                    }
+                    Err(e) => (invalid_message_reply(&text, e), false),
+                };
+                let mut sent = send_message(&mut socket, &sub, &reply).await;
+                if resend_state {
+                    // Current state for newly chosen streams
+                    let (keys, history, foreground, text) = {
+                        let s = state.read();
+                        (
+                            ServerMessage::Keys { keys: s.get_keys() },
+                            s.history_message(),
+                            s.last_foreground.clone(),
+                            s.display
+                                .text_mode
+                                .then(|| s.text_message(std::time::Instant::now())),
+                        )
+                    };
+                    sent = sent && send_message(&mut socket, &sub, &keys).await;
+                    sent = sent && send_message(&mut socket, &sub, &history).await;
+                    if let Some(frame) = foreground {
+                        sent = sent && send_frame(&mut socket, &sub, &frame).await;
+                    }
+                    if let Some(text) = text {
+                        sent = sent && send_message(&mut socket, &sub, &text).await;
+                    }
+                }
+                if !sent {
+                    break;
+                }
+                continue;
+            }
+        };
+
+        if !deliver_frames(&mut socket, &sub, frames, &mut last_seq).await {
             break;
         }
     }
 }
 
+// Sends bus frames in order, skipping ones already delivered; false once the connection is done
+async fn deliver_frames(
+    socket: &mut WebSocket,
+    sub: &Subscription,
+    frames: Vec<Arc<Frame>>,
+    last_seq: &mut u64,
+) -> bool {
+    for frame in frames {
+        if !frame.transient {
+            // Already delivered by an earlier replay
+            if frame.seq <= *last_seq {
+                continue;
+            }
+            *last_seq = frame.seq;
+        }
+        if !send_frame(socket, sub, &frame).await {
+            return false;
+        }
+        if frame.last {
+            // Server is stopping: shutdown was just delivered, close
+            let _ = socket.send(Message::Close(None)).await;
+            return false;
+        }
+    }
+    true
+}
+
+#[derive(serde::Deserialize, Default)]
+struct EventsQuery {
+    // Comma separated stream names, e.g. `keys,events,stats`
+    streams: Option<String>,
+    // Resume point for clients that cannot set the Last-Event-ID header
+    last_event_id: Option<u64>,
+}
+
+fn parse_streams(raw: &str) -> Result<Vec<Stream>, String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            serde_json::from_value(json!(name)).map_err(|_| format!("Unknown stream: {}", name))
+        })
+        .collect()
+}
+
+// Server-Sent Events mirror of /ws for clients that can't (or won't) speak WebSocket.
+// Retained bus frames carry their sequence number as the SSE id, so a reconnecting
+// EventSource resumes from Last-Event-ID; transient frames (stats/foreground) carry none.
+async fn sse_handler(
+    Query(query): Query<EventsQuery>,
+    headers: HeaderMap,
+    AxumState(state): AxumState<SharedState>,
+) -> Response {
+    let streams = match query.streams.as_deref() {
+        Some(raw) => match parse_streams(raw) {
+            Ok(streams) => streams,
+            Err(err) => return (StatusCode::BAD_REQUEST, err).into_response(),
+        },
+        None => Stream::defaults(),
+    };
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .or(query.last_event_id);
+
+    let session = {
+        let s = state.read();
+        // Subscribe while holding the lock so nothing lands between the replay and the live feed
+        let mut session = SseSession {
+            state: state.clone(),
+            streams: streams.into_iter().collect(),
+            rx: s.bus.subscribe(),
+            last_seq: 0,
+            pending: VecDeque::new(),
+            closed: false,
+        };
+        let caught_up = last_event_id.map(|seq| (seq, catch_up(&s, seq)));
+        // The hello id is the point the stream continues from, so a later reconnect resumes there
+        let hello_id = match &caught_up {
+            Some((seq, CatchUp::Replay(_))) => *seq,
+            _ => s.bus.seq(),
+        };
+        session.last_seq = hello_id;
+        session.push(
+            &hello_message(&s, hello_id, vec![Encoding::Json]),
+            Some(hello_id),
+        );
+        match caught_up {
+            Some((_, CatchUp::Replay(frames))) => {
+                for frame in frames {
+                    session.push_frame(&frame);
+                }
+            }
+            Some((_, CatchUp::Resync(_, resync))) => session.push(&resync, None),
+            None => {}
+        }
+        // History goes to every new connection anyway
+        if last_event_id.is_some() {
+            session.push(&s.config_message(), None);
+        }
+        if let Some(frame) = &s.last_foreground {
+            session.push_frame(frame);
+        }
+        session.push(&s.history_message(), None);
+        if s.display.text_mode {
+            session.push(&s.text_message(std::time::Instant::now()), None);
+        }
+        session
+    };
+
+    let stream = futures_util::stream::unfold(session, |mut session| async move {
+        let event = session.next_event().await?;
+        Some((Ok::<Event, Infallible>(event), session))
+    });
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+struct SseSession {
+    state: SharedState,
+    streams: HashSet<Stream>,
+    rx: broadcast::Receiver<Arc<Frame>>,
+    // Last retained frame queued for this client
+    last_seq: u64,
+    pending: VecDeque<Event>,
+    closed: bool,
+}
+
+impl SseSession {
+    fn wants(&self, stream: Option<Stream>) -> bool {
+        stream.is_none_or(|stream| self.streams.contains(&stream))
+    }
+
+    fn push(&mut self, message: &ServerMessage, seq: Option<u64>) {
+        if !self.wants(message.stream()) {
+            return;
+        }
+        let mut event = Event::default().data(message.to_json());
+        if let Some(seq) = seq {
+            event = event.id(seq.to_string());
+        }
+        self.pending.push_back(event);
+    }
+
+    fn push_frame(&mut self, frame: &Frame) {
+        if !frame.transient {
+            // Already queued by an earlier replay
+            if frame.seq <= self.last_seq {
+                return;
+            }
+            self.last_seq = frame.seq;
+        }
+        if frame.last {
+            self.closed = true;
+        }
+        if !self.wants(frame.stream) {
+            return;
+        }
+        let mut event = Event::default().data(&*frame.json);
+        if !frame.transient {
+            event = event.id(frame.seq.to_string());
+        }
+        self.pending.push_back(event);
+    }
+
+    async fn next_event(&mut self) -> Option<Event> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            if self.closed {
+                return None;
+            }
+
+            match self.rx.recv().await {
+                Ok(frame) => self.push_frame(&frame),
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    let (caught_up, snapshots) = {
+                        let s = self.state.read();
+                        (catch_up(&s, self.last_seq), catch_up_snapshots(&s))
+                    };
+                    for snapshot in &snapshots {
+                        self.push(snapshot, None);
+                    }
+                    match caught_up {
+                        CatchUp::Replay(frames) => {
+                            for frame in frames {
+                                self.push_frame(&frame);
+                            }
+                        }
+                        CatchUp::Resync(seq, resync) => {
+                            self.last_seq = seq;
+                            self.push(&resync, None);
+                        }
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
 async fn api_windows() -> impl IntoResponse {
     let windows = window_info::get_all_windows();
     Json(windows)
@@ -611,6 +1321,7 @@ async fn api_set_target(
 ) -> impl IntoResponse {
     let mut state_lock = state.write();
     state_lock.target_config = payload.clone();
+    state_lock.filter_generation += 1;
     state_lock.clear_keys();
 
     // Save to registry
@@ -725,28 +1436,14 @@ async fn api_set_overlay_config(
     }
 
     // Save overlay config to registry
-    let _ = crate::settings::save_overlay_config(
-        overlay.fade_in_ms,
-        overlay.fade_out_ms,
-        &overlay.chip_bg,
-        &overlay.chip_fg,
-        overlay.chip_gap,
-        overlay.chip_pad_v,
-        overlay.chip_pad_h,
-        overlay.chip_radius,
-        overlay.chip_font_px,
-        overlay.chip_font_weight,
-        &overlay.background,
-        overlay.cols,
-        overlay.rows,
-        &overlay.align,
-        &overlay.direction,
-        &overlay.color_mode,
-        &overlay.grad_color1,
-        &overlay.grad_color2,
-        &overlay.grad_dir,
-        &overlay.overlay_mode,
-    );
+    let _ = crate::settings::save_overlay(overlay);
+
+    let overlay = Box::new(overlay.clone());
+    state_lock.broadcast_transient(ServerMessage::ConfigChanged {
+        overlay: Some(overlay),
+        key_style: None,
+        key_images: None,
+    });
 
     Json(json!({ "ok": true }))
 }
@@ -807,6 +1504,12 @@ async fn api_set_key_images(
     // Save to persistent storage
     let _ = crate::settings::save_key_images_config(&payload);
 
+    state_lock.broadcast_transient(ServerMessage::ConfigChanged {
+        overlay: None,
+        key_style: None,
+        key_images: Some(Box::new(payload)),
+    });
+
     Json(json!({ "ok": true }))
 }
 
@@ -825,5 +1528,719 @@ async fn api_set_key_style(
     // Save to persistent storage
     let _ = crate::settings::save_key_style_config(&payload);
 
+    state_lock.broadcast_transient(ServerMessage::ConfigChanged {
+        overlay: None,
+        key_style: Some(Box::new(payload)),
+        key_images: None,
+    });
+
+    Json(json!({ "ok": true }))
+}
+
+async fn api_get_profiles(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let state_lock = state.read();
+    Json(serde_json::to_value(&state_lock.profiles).unwrap_or(json!({})))
+}
+
+#[derive(serde::Deserialize)]
+struct ProfileRequest {
+    name: String,
+}
+
+fn profile_result(result: Result<(), String>) -> Json<serde_json::Value> {
+    match result {
+        Ok(()) => Json(json!({ "ok": true })),
+        Err(error) => Json(json!({ "ok": false, "error": error })),
+    }
+}
+
+// Save the current target/overlay/style as a profile
+async fn api_save_profile(
+    AxumState(state): AxumState<SharedState>,
+    Json(payload): Json<ProfileRequest>,
+) -> impl IntoResponse {
+    let mut state_lock = state.write();
+    profile_result(crate::commands::save_profile(
+        &mut state_lock,
+        &payload.name,
+    ))
+}
+
+async fn api_activate_profile(
+    AxumState(state): AxumState<SharedState>,
+    Json(payload): Json<ProfileRequest>,
+) -> impl IntoResponse {
+    let mut state_lock = state.write();
+    profile_result(crate::commands::switch_profile(
+        &mut state_lock,
+        &payload.name,
+    ))
+}
+
+async fn api_delete_profile(
+    AxumState(state): AxumState<SharedState>,
+    Json(payload): Json<ProfileRequest>,
+) -> impl IntoResponse {
+    let mut state_lock = state.write();
+    profile_result(crate::commands::delete_profile(
+        &mut state_lock,
+        &payload.name,
+    ))
+}
+
+// Same shape as the `stats` stream message
+async fn api_get_stats(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let state_lock = state.read();
+    Json(serde_json::to_value(stats_message(&state_lock)).unwrap_or(json!({})))
+}
+
+async fn api_reset_stats(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let mut state_lock = state.write();
+    let _ = crate::commands::execute(&mut state_lock, &crate::protocol::Command::ResetStats);
+    Json(json!({ "ok": true }))
+}
+
+#[derive(serde::Deserialize, Default)]
+struct CountersQuery {
+    // Omitted = active profile
+    profile: Option<String>,
+}
+
+// Lifetime counters of one profile
+async fn api_get_counters(
+    Query(query): Query<CountersQuery>,
+    AxumState(state): AxumState<SharedState>,
+) -> impl IntoResponse {
+    let state_lock = state.read();
+    let profile = query.profile.or_else(|| state_lock.profiles.active.clone());
+    let counter = state_lock
+        .counters
+        .get(profile.as_deref())
+        .cloned()
+        .unwrap_or_default();
+    Json(json!({
+        "profile": profile,
+        "total": counter.total,
+        "per_key": counter.per_key,
+    }))
+}
+
+#[derive(serde::Deserialize)]
+struct CountersResetRequest {
+    // Omitted = active profile
+    #[serde(default)]
+    profile: Option<String>,
+    // Omitted = every key of the profile
+    #[serde(default)]
+    key: Option<String>,
+}
+
+async fn api_reset_counters(
+    AxumState(state): AxumState<SharedState>,
+    Json(payload): Json<CountersResetRequest>,
+) -> impl IntoResponse {
+    let mut state_lock = state.write();
+    let profile = payload
+        .profile
+        .or_else(|| state_lock.profiles.active.clone());
+    let reset = state_lock
+        .counters
+        .reset(profile.as_deref(), payload.key.as_deref());
+    crate::commands::flush_counters(&mut state_lock);
+    Json(json!({ "ok": true, "reset": reset }))
+}
+
+// Every profile's counters as a counters.json download
+async fn api_export_counters(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let json = serde_json::to_string_pretty(&state.read().counters).unwrap_or_default();
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"keyviewer-counters.json\"",
+        )
+        .body(Body::from(json))
+        .unwrap()
+}
+
+async fn api_get_strafe(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let state_lock = state.read();
+    let report = state_lock.strafe.report(&state_lock.strafe_config);
+    Json(serde_json::to_value(report).unwrap_or(json!({})))
+}
+
+async fn api_reset_strafe(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let mut state_lock = state.write();
+    state_lock.strafe = crate::strafe::StrafeAnalyzer::new(&state_lock.strafe_config);
+    Json(json!({ "ok": true }))
+}
+
+async fn api_get_strafe_config(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let state_lock = state.read();
+    Json(serde_json::to_value(&state_lock.strafe_config).unwrap_or(json!({})))
+}
+
+// Pairs/buckets may change, so results start over
+async fn api_set_strafe_config(
+    AxumState(state): AxumState<SharedState>,
+    Json(payload): Json<crate::state::StrafeConfig>,
+) -> impl IntoResponse {
+    if payload.bucket_ms == 0 {
+        return Json(json!({ "ok": false, "error": "bucket_ms must be positive" }));
+    }
+    if payload.range_ms > crate::strafe::MAX_RANGE_MS {
+        return Json(json!({
+            "ok": false,
+            "error": format!("range_ms must be at most {}", crate::strafe::MAX_RANGE_MS),
+        }));
+    }
+    if crate::strafe::bucket_count(&payload) > crate::strafe::MAX_BUCKETS {
+        return Json(json!({
+            "ok": false,
+            "error": format!(
+                "range_ms / bucket_ms needs more than {} buckets",
+                crate::strafe::MAX_BUCKETS
+            ),
+        }));
+    }
+    if let Some(pair) = payload
+        .pairs
+        .iter()
+        .find(|[a, b]| a.trim().is_empty() || a.eq_ignore_ascii_case(b))
+    {
+        return Json(json!({
+            "ok": false,
+            "error": format!("Invalid strafe pair: {:?}", pair),
+        }));
+    }
+    let mut state_lock = state.write();
+    state_lock.strafe = crate::strafe::StrafeAnalyzer::new(&payload);
+    state_lock.strafe_config = payload;
+    let _ = crate::settings::save_strafe_config(&state_lock.strafe_config);
+    Json(json!({ "ok": true }))
+}
+
+async fn api_get_diagnostics(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let report = state.read().diagnostics.report(std::time::Instant::now());
+    Json(serde_json::to_value(report).unwrap_or(json!({})))
+}
+
+#[derive(serde::Deserialize)]
+struct DiagnosticsRequest {
+    enabled: bool,
+    #[serde(default = "default_chatter_ms")]
+    chatter_ms: u64,
+}
+
+fn default_chatter_ms() -> u64 {
+    crate::diagnostics::DEFAULT_CHATTER_MS
+}
+
+// Start (always a fresh run) or stop diagnostics
+async fn api_set_diagnostics(
+    AxumState(state): AxumState<SharedState>,
+    Json(payload): Json<DiagnosticsRequest>,
+) -> impl IntoResponse {
+    let mut state_lock = state.write();
+    state_lock.diagnostics.set_enabled(
+        payload.enabled,
+        payload.chatter_ms,
+        std::time::Instant::now(),
+    );
+    Json(json!({ "ok": true, "enabled": payload.enabled }))
+}
+
+async fn api_export_diagnostics(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let report = state.read().diagnostics.report(std::time::Instant::now());
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .header(
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"keyviewer-diagnostics.txt\"",
+        )
+        .body(Body::from(report.to_text()))
+        .unwrap()
+}
+
+// Status of the current recording and every recording on disk
+async fn api_get_recordings(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let status = state.read().recorder.status();
+    let files = crate::settings::recordings_dir()
+        .map(|dir| crate::recorder::list(&dir))
+        .unwrap_or_default();
+    Json(json!({ "current": status, "recordings": files }))
+}
+
+async fn api_start_recording(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let dir = match crate::settings::recordings_dir() {
+        Ok(dir) => dir,
+        Err(error) => return Json(json!({ "ok": false, "error": error })),
+    };
+    let mut state_lock = state.write();
+    let profile = state_lock.profiles.active.clone();
+    match state_lock
+        .recorder
+        .start(dir, profile.as_deref(), crate::state::unix_ms())
+    {
+        Ok(()) => Json(json!({ "ok": true, "current": state_lock.recorder.status() })),
+        Err(error) => Json(json!({ "ok": false, "error": error })),
+    }
+}
+
+async fn api_stop_recording(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    match state.write().recorder.stop() {
+        Some(status) => Json(json!({ "ok": true, "stopped": status })),
+        None => Json(json!({ "ok": false, "error": "Not recording" })),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct RecordingRequest {
+    id: String,
+}
+
+async fn api_delete_recording(
+    AxumState(state): AxumState<SharedState>,
+    Json(payload): Json<RecordingRequest>,
+) -> impl IntoResponse {
+    let dir = match crate::settings::recordings_dir() {
+        Ok(dir) => dir,
+        Err(error) => return Json(json!({ "ok": false, "error": error })),
+    };
+    let state_lock = state.read();
+    match crate::recorder::delete(&state_lock.recorder, &dir, &payload.id) {
+        Ok(true) => Json(json!({ "ok": true })),
+        Ok(false) => Json(json!({ "ok": false, "error": "Recording not found" })),
+        Err(error) => Json(json!({ "ok": false, "error": error })),
+    }
+}
+
+// One recording as a .jsonl download; the parts of a session are joined in order
+// (each keeps its header line)
+async fn api_get_recording_file(Query(query): Query<RecordingRequest>) -> Response {
+    let paths = crate::settings::recordings_dir()
+        .ok()
+        .and_then(|dir| crate::recorder::session_paths(&dir, &query.id));
+    let contents = paths.and_then(|paths| {
+        paths.iter().try_fold(Vec::new(), |mut contents, path| {
+            contents.extend(std::fs::read(path).ok()?);
+            Some(contents)
+        })
+    });
+    let Some(contents) = contents else {
+        return (StatusCode::NOT_FOUND, "Recording not found").into_response();
+    };
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}.jsonl\"", query.id),
+        )
+        .body(Body::from(contents))
+        .unwrap()
+}
+
+#[derive(serde::Deserialize)]
+struct ExportQuery {
+    id: String,
+    // "events" (default) | "keys"
+    table: Option<String>,
+}
+
+// A recording (every part of its session) as CSV: every event, or per-key statistics
+async fn api_export_csv(Query(query): Query<ExportQuery>) -> Response {
+    let table = query.table.as_deref().unwrap_or("events");
+    let Some(table) = crate::export::Table::parse(table) else {
+        return (StatusCode::BAD_REQUEST, format!("Unknown table: {}", table)).into_response();
+    };
+    let csv = crate::settings::recordings_dir()
+        .and_then(|dir| crate::replay::session_paths(&dir, &query.id))
+        .and_then(|paths| crate::export::render(&paths, table));
+    let csv = match csv {
+        Ok(csv) => csv,
+        Err(error) => return (StatusCode::NOT_FOUND, error).into_response(),
+    };
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/csv; charset=utf-8")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}{}\"", query.id, table.suffix()),
+        )
+        .body(Body::from(csv))
+        .unwrap()
+}
+
+#[derive(serde::Deserialize)]
+struct SubtitlesQuery {
+    id: String,
+    // "srt" (default) | "ass"
+    format: Option<String>,
+    merge_chords: Option<bool>,
+    min_duration_ms: Option<u64>,
+    offset_ms: Option<i64>,
+}
+
+// A recording (every part of its session) as an SRT/ASS subtitle download
+async fn api_get_recording_subtitles(
+    AxumState(state): AxumState<SharedState>,
+    Query(query): Query<SubtitlesQuery>,
+) -> Response {
+    let format = query.format.as_deref().unwrap_or("srt");
+    let Some(format) = crate::subtitles::Format::parse(format) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Unknown format: {}", format),
+        )
+            .into_response();
+    };
+    let events =
+        crate::settings::recordings_dir().and_then(|dir| crate::replay::load(&dir, &query.id));
+    let events = match events {
+        Ok(events) => events,
+        Err(error) => return (StatusCode::NOT_FOUND, error).into_response(),
+    };
+    let options = {
+        let state_lock = state.read();
+        crate::subtitles::SubtitleOptions {
+            format,
+            merge_chords: query.merge_chords.unwrap_or(true),
+            min_duration_ms: query
+                .min_duration_ms
+                .unwrap_or(crate::subtitles::DEFAULT_MIN_DURATION_MS),
+            offset_ms: query.offset_ms.unwrap_or(0),
+            separator: state_lock.display.separator.clone(),
+            modifier_order: state_lock.display.modifier_order.clone(),
+        }
+    };
+    let content_type = match format {
+        crate::subtitles::Format::Srt => "application/x-subrip; charset=utf-8",
+        crate::subtitles::Format::Ass => "text/x-ssa; charset=utf-8",
+    };
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"{}.{}\"",
+                query.id,
+                format.extension()
+            ),
+        )
+        .body(Body::from(crate::subtitles::render(&events, &options)))
+        .unwrap()
+}
+
+async fn api_get_replay(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let state_lock = state.read();
+    let status = state_lock
+        .replay
+        .as_ref()
+        .map(|replay| replay.status(std::time::Instant::now()));
+    Json(json!({ "active": status.is_some(), "replay": status }))
+}
+
+fn default_replay_speed() -> f64 {
+    1.0
+}
+
+fn invalid_replay_speed(speed: f64) -> Option<String> {
+    let range = crate::replay::MIN_SPEED..=crate::replay::MAX_SPEED;
+    (!range.contains(&speed)).then(|| {
+        format!(
+            "speed must be between {} and {}",
+            crate::replay::MIN_SPEED,
+            crate::replay::MAX_SPEED
+        )
+    })
+}
+
+#[derive(serde::Deserialize)]
+struct ReplayStartRequest {
+    id: String,
+    #[serde(default = "default_replay_speed")]
+    speed: f64,
+    #[serde(default)]
+    position_ms: f64,
+    #[serde(default)]
+    paused: bool,
+}
+
+// Loads a recording (every part of its session) and plays it in place of live input
+async fn api_start_replay(
+    AxumState(state): AxumState<SharedState>,
+    Json(payload): Json<ReplayStartRequest>,
+) -> impl IntoResponse {
+    if let Some(error) = invalid_replay_speed(payload.speed) {
+        return Json(json!({ "ok": false, "error": error }));
+    }
+    let events =
+        crate::settings::recordings_dir().and_then(|dir| crate::replay::load(&dir, &payload.id));
+    let events = match events {
+        Ok(events) => events,
+        Err(error) => return Json(json!({ "ok": false, "error": error })),
+    };
+    let now = std::time::Instant::now();
+    let mut replay = crate::replay::Replay::new(payload.id, events, payload.speed, now);
+    replay.set_paused(payload.paused, now);
+    let mut state_lock = state.write();
+    crate::commands::start_replay(&mut state_lock, replay);
+    if payload.position_ms > 0.0 {
+        let _ = crate::commands::seek_replay(&mut state_lock, payload.position_ms);
+    }
+    let status = state_lock.replay.as_ref().map(|replay| replay.status(now));
+    Json(json!({ "ok": true, "replay": status }))
+}
+
+#[derive(serde::Deserialize)]
+struct ReplayPauseRequest {
+    paused: bool,
+}
+
+async fn api_pause_replay(
+    AxumState(state): AxumState<SharedState>,
+    Json(payload): Json<ReplayPauseRequest>,
+) -> impl IntoResponse {
+    let mut state_lock = state.write();
+    let Some(replay) = state_lock.replay.as_mut() else {
+        return Json(json!({ "ok": false, "error": "No replay running" }));
+    };
+    replay.set_paused(payload.paused, std::time::Instant::now());
+    Json(json!({ "ok": true, "paused": payload.paused }))
+}
+
+#[derive(serde::Deserialize)]
+struct ReplaySeekRequest {
+    position_ms: f64,
+}
+
+async fn api_seek_replay(
+    AxumState(state): AxumState<SharedState>,
+    Json(payload): Json<ReplaySeekRequest>,
+) -> impl IntoResponse {
+    let mut state_lock = state.write();
+    match crate::commands::seek_replay(&mut state_lock, payload.position_ms) {
+        Ok(()) => Json(json!({ "ok": true })),
+        Err(error) => Json(json!({ "ok": false, "error": error })),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ReplaySpeedRequest {
+    speed: f64,
+}
+
+async fn api_set_replay_speed(
+    AxumState(state): AxumState<SharedState>,
+    Json(payload): Json<ReplaySpeedRequest>,
+) -> impl IntoResponse {
+    if let Some(error) = invalid_replay_speed(payload.speed) {
+        return Json(json!({ "ok": false, "error": error }));
+    }
+    let mut state_lock = state.write();
+    let Some(replay) = state_lock.replay.as_mut() else {
+        return Json(json!({ "ok": false, "error": "No replay running" }));
+    };
+    replay.set_speed(payload.speed, std::time::Instant::now());
+    Json(json!({ "ok": true, "speed": payload.speed }))
+}
+
+async fn api_stop_replay(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    if crate::commands::stop_replay(&mut state.write()) {
+        Json(json!({ "ok": true }))
+    } else {
+        Json(json!({ "ok": false, "error": "No replay running" }))
+    }
+}
+
+#[derive(serde::Deserialize, Default)]
+struct HeatmapQuery {
+    // "session" (default, since the last stats reset) | "lifetime"
+    range: Option<String>,
+    // Lifetime counters of this profile instead of the active one
+    profile: Option<String>,
+    // Time range in unix ms, or the last `minutes`; takes precedence over `range`
+    from: Option<u64>,
+    to: Option<u64>,
+    minutes: Option<u64>,
+    // "keyviewer" (default when the canvas has cells) | "ansi"
+    layout: Option<String>,
+}
+
+async fn api_heatmap_svg(
+    Query(query): Query<HeatmapQuery>,
+    AxumState(state): AxumState<SharedState>,
+) -> impl IntoResponse {
+    use crate::heatmap::{ansi_layout, canonical_label, key_viewer_layout, render_svg};
+    use std::collections::HashMap;
+
+    let (counts, title, layout) = {
+        let s = state.read();
+        let canonical = |per_key: &std::collections::BTreeMap<String, u64>| {
+            let mut counts: HashMap<String, u64> = HashMap::new();
+            for (label, count) in per_key {
+                *counts.entry(canonical_label(label)).or_insert(0) += count;
+            }
+            counts
+        };
+        let (counts, title) =
+            if query.from.is_some() || query.to.is_some() || query.minutes.is_some() {
+                let now = crate::state::unix_ms();
+                let from = match query.minutes {
+                    Some(minutes) => now.saturating_sub(minutes.saturating_mul(60_000)),
+                    None => query.from.unwrap_or(0),
+                };
+                let to = query.to.unwrap_or(now);
+                let title = match query.minutes {
+                    Some(minutes) => format!("Last {} min", minutes),
+                    None => "Time range".to_string(),
+                };
+                (s.press_log.counts(from, to), title)
+            } else {
+                match query.range.as_deref().unwrap_or("session") {
+                    "session" => (canonical(s.stats.per_key()), "Session".to_string()),
+                    "lifetime" => {
+                        let profile = query.profile.clone().or_else(|| s.profiles.active.clone());
+                        let counter = s
+                            .counters
+                            .get(profile.as_deref())
+                            .cloned()
+                            .unwrap_or_default();
+                        let title =
+                            format!("Lifetime ({})", profile.as_deref().unwrap_or("default"));
+                        (canonical(&counter.per_key), title)
+                    }
+                    other => {
+                        return (StatusCode::BAD_REQUEST, format!("Unknown range: {}", other))
+                            .into_response()
+                    }
+                }
+            };
+        let layout = match query.layout.as_deref() {
+            Some("ansi") => ansi_layout(),
+            None | Some("keyviewer") => {
+                key_viewer_layout(&s.app_config.key_style).unwrap_or_else(ansi_layout)
+            }
+            Some(other) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    format!("Unknown layout: {}", other),
+                )
+                    .into_response()
+            }
+        };
+        (counts, title, layout)
+    };
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "image/svg+xml; charset=utf-8")
+        .header(header::CACHE_CONTROL, "no-cache, no-store")
+        .body(Body::from(render_svg(&layout, &counts, &title)))
+        .unwrap()
+        .into_response()
+}
+
+async fn api_get_pause(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let state_lock = state.read();
+    Json(serde_json::to_value(crate::commands::status_message(&state_lock)).unwrap_or(json!({})))
+}
+
+#[derive(serde::Deserialize)]
+struct PauseRequest {
+    // Omitted = toggle
+    #[serde(default)]
+    paused: Option<bool>,
+}
+
+async fn api_set_pause(
+    AxumState(state): AxumState<SharedState>,
+    Json(payload): Json<PauseRequest>,
+) -> impl IntoResponse {
+    let mut state_lock = state.write();
+    let paused = payload.paused.unwrap_or(!state_lock.capture_paused);
+    crate::commands::set_capture_paused(&mut state_lock, paused);
+    Json(json!({ "ok": true, "paused": paused }))
+}
+
+async fn api_get_privacy(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let state_lock = state.read();
+    Json(serde_json::to_value(&state_lock.privacy).unwrap_or(json!({})))
+}
+
+async fn api_set_privacy(
+    AxumState(state): AxumState<SharedState>,
+    Json(payload): Json<crate::state::PrivacyConfig>,
+) -> impl IntoResponse {
+    if payload.mask_char.is_empty() {
+        return Json(json!({ "ok": false, "error": "mask_char is empty" }));
+    }
+    let invalid_rule = payload.blocklist.iter().find(|rule| {
+        !matches!(rule.mode.as_str(), "process" | "title" | "class") || rule.value.trim().is_empty()
+    });
+    if let Some(rule) = invalid_rule {
+        return Json(json!({
+            "ok": false,
+            "error": format!("Invalid blocklist rule: {} = {:?}", rule.mode, rule.value),
+        }));
+    }
+    let mut state_lock = state.write();
+    crate::commands::set_privacy(&mut state_lock, payload);
+    Json(json!({ "ok": true }))
+}
+
+async fn api_get_hotkeys(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let state_lock = state.read();
+    Json(serde_json::to_value(&state_lock.hotkeys).unwrap_or(json!({})))
+}
+
+async fn api_set_hotkeys(
+    AxumState(state): AxumState<SharedState>,
+    Json(mut payload): Json<crate::state::HotkeysConfig>,
+) -> impl IntoResponse {
+    // Store chords in the form the watcher compares against (LCTRL -> CTRL, lowercase -> upper)
+    for binding in &mut payload.bindings {
+        binding.keys = binding
+            .keys
+            .iter()
+            .map(|key| crate::hotkeys::normalize_label(key))
+            .filter(|key| !key.is_empty())
+            .collect();
+        if binding.keys.is_empty() {
+            return Json(json!({
+                "ok": false,
+                "error": format!("Hotkey for {:?} has no keys", binding.action),
+            }));
+        }
+    }
+    let mut state_lock = state.write();
+    state_lock.hotkeys = payload;
+    let _ = crate::settings::save_hotkeys_config(&state_lock.hotkeys);
+    Json(json!({ "ok": true }))
+}
+
+async fn api_get_display_config(AxumState(state): AxumState<SharedState>) -> impl IntoResponse {
+    let state_lock = state.read();
+    Json(serde_json::to_value(&state_lock.display).unwrap_or(json!({})))
+}
+
+async fn api_set_display_config(
+    AxumState(state): AxumState<SharedState>,
+    Json(mut payload): Json<crate::state::DisplayConfig>,
+) -> impl IntoResponse {
+    // Same modifier names as chord labels (LCTRL -> CTRL)
+    payload.modifier_order = payload
+        .modifier_order
+        .iter()
+        .map(|m| crate::hotkeys::normalize_label(m))
+        .collect();
+    let mut state_lock = state.write();
+    let text_was_on = state_lock.display.text_mode;
+    state_lock.display = payload;
+    let _ = crate::settings::save_display_config(&state_lock.display);
+    if text_was_on && !state_lock.display.text_mode {
+        // Blank the text overlays are still showing
+        state_lock.typed_text.clear();
+        let message = state_lock.text_message(std::time::Instant::now());
+        state_lock.broadcast(message);
+    }
     Json(json!({ "ok": true }))
 }
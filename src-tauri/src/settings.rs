@@ -1,6 +1,10 @@
 #![cfg_attr(target_os = "macos", allow(unexpected_cfgs))]
 
-use crate::state::{KeyImagesConfig, KeyStyleConfig};
+use crate::counters::LifetimeCounters;
+use crate::state::{
+    DisplayConfig, HotkeysConfig, KeyImagesConfig, KeyStyleConfig, OverlayConfig, PrivacyConfig,
+    ProfilesConfig, StrafeConfig,
+};
 use serde::{Deserialize, Serialize};
 
 #[cfg(target_os = "windows")]
@@ -299,6 +303,32 @@ pub fn load_target_config() -> (String, Option<String>) {
     }
 }
 
+// Save a whole OverlayConfig (see save_overlay_config)
+pub fn save_overlay(overlay: &OverlayConfig) -> Result<(), String> {
+    save_overlay_config(
+        overlay.fade_in_ms,
+        overlay.fade_out_ms,
+        &overlay.chip_bg,
+        &overlay.chip_fg,
+        overlay.chip_gap,
+        overlay.chip_pad_v,
+        overlay.chip_pad_h,
+        overlay.chip_radius,
+        overlay.chip_font_px,
+        overlay.chip_font_weight,
+        &overlay.background,
+        overlay.cols,
+        overlay.rows,
+        &overlay.align,
+        &overlay.direction,
+        &overlay.color_mode,
+        &overlay.grad_color1,
+        &overlay.grad_color2,
+        &overlay.grad_dir,
+        &overlay.overlay_mode,
+    )
+}
+
 // Save/Load Overlay Config to/from Registry or UserDefaults
 #[allow(clippy::too_many_arguments)]
 pub fn save_overlay_config(
@@ -685,6 +715,186 @@ pub fn load_key_style_config() -> KeyStyleConfig {
     KeyStyleConfig::default()
 }
 
+// Save/Load Profiles to/from file (JSON)
+pub fn save_profiles_config(config: &ProfilesConfig) -> Result<(), String> {
+    let config_dir = get_config_dir()?;
+    let file_path = config_dir.join("profiles.json");
+
+    let json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+
+    std::fs::write(&file_path, json)
+        .map_err(|e| format!("Failed to write profiles file: {}", e))?;
+
+    Ok(())
+}
+
+pub fn load_profiles_config() -> ProfilesConfig {
+    if let Ok(config_dir) = get_config_dir() {
+        let file_path = config_dir.join("profiles.json");
+        if let Ok(json) = std::fs::read_to_string(&file_path) {
+            if let Ok(config) = serde_json::from_str(&json) {
+                return config;
+            }
+        }
+    }
+    ProfilesConfig::default()
+}
+
+pub fn save_privacy_config(config: &PrivacyConfig) -> Result<(), String> {
+    let config_dir = get_config_dir()?;
+    let file_path = config_dir.join("privacy.json");
+
+    let json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize privacy config: {}", e))?;
+
+    std::fs::write(&file_path, json).map_err(|e| format!("Failed to write privacy file: {}", e))?;
+
+    Ok(())
+}
+
+pub fn load_privacy_config() -> PrivacyConfig {
+    if let Ok(config_dir) = get_config_dir() {
+        let file_path = config_dir.join("privacy.json");
+        if let Ok(json) = std::fs::read_to_string(&file_path) {
+            if let Ok(config) = serde_json::from_str(&json) {
+                return config;
+            }
+        }
+    }
+    PrivacyConfig::default()
+}
+
+pub fn save_hotkeys_config(config: &HotkeysConfig) -> Result<(), String> {
+    let config_dir = get_config_dir()?;
+    let file_path = config_dir.join("hotkeys.json");
+
+    let json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize hotkeys config: {}", e))?;
+
+    std::fs::write(&file_path, json).map_err(|e| format!("Failed to write hotkeys file: {}", e))?;
+
+    Ok(())
+}
+
+pub fn load_hotkeys_config() -> HotkeysConfig {
+    if let Ok(config_dir) = get_config_dir() {
+        let file_path = config_dir.join("hotkeys.json");
+        if let Ok(json) = std::fs::read_to_string(&file_path) {
+            if let Ok(config) = serde_json::from_str(&json) {
+                return config;
+            }
+        }
+    }
+    HotkeysConfig::default()
+}
+
+pub fn save_display_config(config: &DisplayConfig) -> Result<(), String> {
+    let config_dir = get_config_dir()?;
+    let file_path = config_dir.join("display.json");
+
+    let json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize display config: {}", e))?;
+
+    std::fs::write(&file_path, json).map_err(|e| format!("Failed to write display file: {}", e))?;
+
+    Ok(())
+}
+
+pub fn load_display_config() -> DisplayConfig {
+    if let Ok(config_dir) = get_config_dir() {
+        let file_path = config_dir.join("display.json");
+        if let Ok(json) = std::fs::read_to_string(&file_path) {
+            if let Ok(config) = serde_json::from_str(&json) {
+                return config;
+            }
+        }
+    }
+    DisplayConfig::default()
+}
+
+pub fn save_strafe_config(config: &StrafeConfig) -> Result<(), String> {
+    let config_dir = get_config_dir()?;
+    let file_path = config_dir.join("strafe.json");
+
+    let json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize strafe config: {}", e))?;
+
+    std::fs::write(&file_path, json).map_err(|e| format!("Failed to write strafe file: {}", e))?;
+
+    Ok(())
+}
+
+pub fn load_strafe_config() -> StrafeConfig {
+    if let Ok(config_dir) = get_config_dir() {
+        let file_path = config_dir.join("strafe.json");
+        if let Ok(json) = std::fs::read_to_string(&file_path) {
+            if let Ok(config) = serde_json::from_str(&json) {
+                return config;
+            }
+        }
+    }
+    StrafeConfig::default()
+}
+
+pub fn save_lifetime_counters(counters: &LifetimeCounters) -> Result<(), String> {
+    let config_dir = get_config_dir()?;
+    let file_path = config_dir.join("counters.json");
+
+    let json = serde_json::to_string_pretty(counters)
+        .map_err(|e| format!("Failed to serialize counters: {}", e))?;
+
+    // Write next to the real file and rename over it, so a crash mid-write
+    // never leaves a truncated counters.json behind
+    let temp_path = config_dir.join("counters.json.tmp");
+    std::fs::write(&temp_path, json)
+        .map_err(|e| format!("Failed to write counters file: {}", e))?;
+    std::fs::rename(&temp_path, &file_path)
+        .map_err(|e| format!("Failed to replace counters file: {}", e))?;
+
+    Ok(())
+}
+
+pub fn load_lifetime_counters() -> LifetimeCounters {
+    if let Ok(config_dir) = get_config_dir() {
+        let file_path = config_dir.join("counters.json");
+        if let Ok(json) = std::fs::read_to_string(&file_path) {
+            match serde_json::from_str(&json) {
+                Ok(counters) => return counters,
+                // Keep the unreadable file aside instead of letting the next flush
+                // overwrite it with zeroed counters
+                Err(error) => {
+                    let stamp = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map(|d| d.as_millis())
+                        .unwrap_or(0);
+                    let backup = config_dir.join(format!("counters.json.bad-{}", stamp));
+                    match std::fs::rename(&file_path, &backup) {
+                        Ok(()) => eprintln!(
+                            "[Counters] counters.json is unreadable ({}); moved it to {}",
+                            error,
+                            backup.display()
+                        ),
+                        Err(e) => eprintln!(
+                            "[Counters] counters.json is unreadable ({}) and could not be backed up: {}",
+                            error, e
+                        ),
+                    }
+                }
+            }
+        }
+    }
+    LifetimeCounters::default()
+}
+
+// Session recordings (`recorder.rs`) live in their own subdirectory
+pub fn recordings_dir() -> Result<std::path::PathBuf, String> {
+    let path = get_config_dir()?.join("recordings");
+    std::fs::create_dir_all(&path)
+        .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+    Ok(path)
+}
+
 fn get_config_dir() -> Result<std::path::PathBuf, String> {
     #[cfg(target_os = "windows")]
     {
@@ -1,7 +1,19 @@
+use crate::bus::{EventBus, Frame};
+use crate::counters::LifetimeCounters;
+use crate::diagnostics::Diagnostics;
+use crate::heatmap::PressLog;
+use crate::history::KeyHistory;
+use crate::hotkeys::{self, normalize_label};
+use crate::protocol::{Sequenced, ServerMessage};
+use crate::recorder::Recorder;
+use crate::replay::{EventKind, Replay, ReplayEvent};
+use crate::stats::StatsEngine;
+use crate::strafe::{StrafeAnalyzer, Transition};
+use crate::typed_text::{self, TypedText};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::watch;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TargetConfig {
@@ -232,6 +244,11 @@ pub struct KeyViewerCell {
     pub fg_override: Option<String>,
     #[serde(rename = "radiusOverride", default)]
     pub radius_override: Option<f64>,
+    /// Live statistic shown under the label: "count" (this key's presses),
+    /// "kps", "apm", "peak_kps", "presses" (session total), "lifetime"
+    /// (active profile's total across restarts), "bpm" or "unstable_rate" (rhythm)
+    #[serde(default)]
+    pub stat: Option<String>,
 }
 
 fn default_kv_canvas_w() -> f64 {
@@ -308,6 +325,28 @@ impl Default for KeyStyleConfig {
     }
 }
 
+impl KeyStyleConfig {
+    // Raw style group `label` resolves to in the given overlay mode.
+    // Same rule as the overlay: per-mode groups (legacy list as fallback), first match wins.
+    pub fn raw_group_for(&self, overlay_mode: &str, label: &str) -> Option<&serde_json::Value> {
+        let groups = if overlay_mode == "keyviewer" {
+            &self.raw_style_groups_kv
+        } else {
+            &self.raw_style_groups_queue
+        };
+        let groups = match groups.as_array() {
+            Some(groups) if !groups.is_empty() => groups,
+            _ => self.raw_style_groups.as_array()?,
+        };
+        groups.iter().find(|group| {
+            group["type"] == "all"
+                || group["keys"]
+                    .as_array()
+                    .is_some_and(|keys| keys.iter().any(|key| key == label))
+        })
+    }
+}
+
 // ============= Legacy structures (keep for backward compatibility) =============
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -363,6 +402,309 @@ impl Default for AppConfig {
     }
 }
 
+/// Named snapshot of target + overlay look, switched from the control page or /ws
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub target: TargetConfig,
+    pub overlay: OverlayConfig,
+    #[serde(rename = "keyStyle", default)]
+    pub key_style: KeyStyleConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ProfilesConfig {
+    // Name of the profile applied last (None = settings edited without a profile)
+    #[serde(default)]
+    pub active: Option<String>,
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+}
+
+/// Foreground window whose input is never captured, whatever the target mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockRule {
+    pub mode: String, // "process" | "title" | "class"
+    pub value: String,
+}
+
+impl BlockRule {
+    fn new(mode: &str, value: &str) -> Self {
+        Self {
+            mode: mode.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+/// Pause masking options and the sensitive-app blocklist
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyConfig {
+    // false = show nothing, true = keep showing presses but with every label replaced
+    #[serde(default)]
+    pub mask: bool,
+    #[serde(default = "default_mask_char")]
+    pub mask_char: String,
+    #[serde(default = "default_blocklist")]
+    pub blocklist: Vec<BlockRule>,
+}
+
+fn default_mask_char() -> String {
+    "•".to_string()
+}
+
+// Password managers plus anything with "password" in the window title
+fn default_blocklist() -> Vec<BlockRule> {
+    vec![
+        BlockRule::new("process", "KeePassXC"),
+        BlockRule::new("process", "KeePass"),
+        BlockRule::new("process", "1Password"),
+        BlockRule::new("process", "Bitwarden"),
+        BlockRule::new("title", "password"),
+    ]
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self {
+            mask: false,
+            mask_char: default_mask_char(),
+            blocklist: default_blocklist(),
+        }
+    }
+}
+
+/// Action a global hotkey triggers (each maps onto a /ws command)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    TogglePause,
+    ClearKeys,
+    NextProfile,
+    PreviousProfile,
+    ToggleOverlay,
+}
+
+/// A chord of overlay labels held together, e.g. ["CTRL", "SHIFT", "P"].
+/// Left/right modifiers are written as CTRL / SHIFT / ALT / META.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeyBinding {
+    pub action: HotkeyAction,
+    pub keys: Vec<String>,
+}
+
+impl HotkeyBinding {
+    fn new(action: HotkeyAction, keys: &[&str]) -> Self {
+        Self {
+            action,
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeysConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    // Keep the keys of a fired chord off the overlay
+    #[serde(default = "default_true")]
+    pub suppress_chord: bool,
+    #[serde(default = "default_hotkey_bindings")]
+    pub bindings: Vec<HotkeyBinding>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+// Hotkeys are observed, not consumed, so the defaults stay off shortcuts that apps use
+// (Ctrl+Shift+P is the command palette / private window)
+fn default_hotkey_bindings() -> Vec<HotkeyBinding> {
+    vec![
+        HotkeyBinding::new(HotkeyAction::TogglePause, &["CTRL", "SHIFT", "F8"]),
+        HotkeyBinding::new(HotkeyAction::ClearKeys, &["CTRL", "SHIFT", "F9"]),
+        HotkeyBinding::new(HotkeyAction::PreviousProfile, &["CTRL", "SHIFT", "F10"]),
+        HotkeyBinding::new(HotkeyAction::NextProfile, &["CTRL", "SHIFT", "F11"]),
+        HotkeyBinding::new(HotkeyAction::ToggleOverlay, &["CTRL", "SHIFT", "F12"]),
+    ]
+}
+
+impl Default for HotkeysConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            suppress_chord: true,
+            bindings: default_hotkey_bindings(),
+        }
+    }
+}
+
+/// How held keys are grouped for display
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayConfig {
+    // Emit a `chord` message when a key is pressed while modifiers are held
+    #[serde(default)]
+    pub chord_mode: bool,
+    // Modifier order inside a chord label; modifiers not listed follow in press order
+    #[serde(default = "default_modifier_order")]
+    pub modifier_order: Vec<String>,
+    #[serde(default = "default_chord_separator")]
+    pub separator: String,
+    // Shift + key is ordinary typing, not a shortcut
+    #[serde(default = "default_true")]
+    pub skip_shift_only: bool,
+    // Build a rolling line of typed text from presses (`text` messages)
+    #[serde(default)]
+    pub text_mode: bool,
+    // Lines kept, including the one being typed
+    #[serde(default = "default_text_max_lines")]
+    pub text_max_lines: usize,
+    // Older text scrolls off the front past this many characters (0 = no limit)
+    #[serde(default = "default_text_max_chars")]
+    pub text_max_chars: usize,
+    // A key repeated more than this many times in a row collapses to `A ×n` (0 = never)
+    #[serde(default = "default_text_collapse_after")]
+    pub text_collapse_after: u32,
+    // Text starts over after this long without a press (0 = never)
+    #[serde(default = "default_text_idle_ms")]
+    pub text_idle_ms: u64,
+    // Recent presses kept in the `history` stream
+    #[serde(default = "default_history_len")]
+    pub history_len: usize,
+    // How long a released key stays in the history
+    #[serde(default = "default_history_linger_ms")]
+    pub history_linger_ms: u64,
+    // A key stays on screen at least this long after its press, however short the tap
+    // (style groups can override with `minVisibleMs`)
+    #[serde(default)]
+    pub min_visible_ms: u64,
+    // Released keys stay this long; pressing again in that window continues the same chip
+    // (style groups can override with `releaseGraceMs`)
+    #[serde(default)]
+    pub release_grace_ms: u64,
+    // Send `repeat` messages while a key auto-repeats, for a repeat indicator on its chip
+    #[serde(default)]
+    pub show_repeats: bool,
+}
+
+fn default_modifier_order() -> Vec<String> {
+    ["CTRL", "ALT", "SHIFT", "META"]
+        .iter()
+        .map(|m| m.to_string())
+        .collect()
+}
+
+fn default_chord_separator() -> String {
+    "+".to_string()
+}
+
+fn default_text_max_lines() -> usize {
+    2
+}
+
+fn default_text_max_chars() -> usize {
+    48
+}
+
+fn default_text_collapse_after() -> u32 {
+    3
+}
+
+fn default_text_idle_ms() -> u64 {
+    4000
+}
+
+fn default_history_len() -> usize {
+    8
+}
+
+fn default_history_linger_ms() -> u64 {
+    1500
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            chord_mode: false,
+            modifier_order: default_modifier_order(),
+            separator: default_chord_separator(),
+            skip_shift_only: true,
+            text_mode: false,
+            text_max_lines: default_text_max_lines(),
+            text_max_chars: default_text_max_chars(),
+            text_collapse_after: default_text_collapse_after(),
+            text_idle_ms: default_text_idle_ms(),
+            history_len: default_history_len(),
+            history_linger_ms: default_history_linger_ms(),
+            min_visible_ms: 0,
+            release_grace_ms: 0,
+            show_repeats: false,
+        }
+    }
+}
+
+/// Counter-strafe analyzer options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrafeConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    // Opposing movement keys, by label
+    #[serde(default = "default_strafe_pairs")]
+    pub pairs: Vec<[String; 2]>,
+    // A release/press gap longer than this is separate movement, not a counter-strafe
+    #[serde(default = "default_strafe_max_gap_ms")]
+    pub max_gap_ms: u64,
+    // Histogram bucket width; buckets cover -range_ms..range_ms (outliers go to the ends)
+    #[serde(default = "default_strafe_bucket_ms")]
+    pub bucket_ms: u64,
+    #[serde(default = "default_strafe_range_ms")]
+    pub range_ms: u64,
+}
+
+fn default_strafe_pairs() -> Vec<[String; 2]> {
+    [["A", "D"], ["W", "S"]]
+        .iter()
+        .map(|[a, b]| [a.to_string(), b.to_string()])
+        .collect()
+}
+
+fn default_strafe_max_gap_ms() -> u64 {
+    200
+}
+
+fn default_strafe_bucket_ms() -> u64 {
+    10
+}
+
+fn default_strafe_range_ms() -> u64 {
+    100
+}
+
+impl Default for StrafeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            pairs: default_strafe_pairs(),
+            max_gap_ms: default_strafe_max_gap_ms(),
+            bucket_ms: default_strafe_bucket_ms(),
+            range_ms: default_strafe_range_ms(),
+        }
+    }
+}
+
+// Wall-clock milliseconds since the Unix epoch
+pub fn unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+// Frames buffered per client before it counts as lagging
+const BUS_CAPACITY: usize = 1024;
+// Retained frames kept for Last-Event-ID / resume replay
+const BUS_HISTORY_LEN: usize = 1024;
+
 pub struct AppState {
     // Map of key code to label for tracking (code -> label)
     pub key_labels: HashMap<u32, String>,
@@ -378,8 +720,52 @@ pub struct AppState {
     pub language: String,
     // Server alive flag to terminate active websocket loops on stop
     pub server_alive: bool,
-    // Outgoing key updates for immediate WS pushes
-    pub event_tx: Option<watch::Sender<Vec<String>>>,
+    // Pre-serialized outgoing messages shared by all WS/SSE clients
+    pub bus: EventBus,
+    // Latest `foreground` frame, handed to clients as soon as they subscribe
+    pub last_foreground: Option<Arc<Frame>>,
+    // Saved profiles and which one is active
+    pub profiles: ProfilesConfig,
+    // While set, new key presses are hidden (or masked, see `privacy`); releases still go through
+    pub capture_paused: bool,
+    // Masking options for the paused state
+    pub privacy: PrivacyConfig,
+    // Bumped whenever `target_config` or the privacy blocklist changes, so input threads
+    // that keep a copy of them know to reload it before the next event
+    pub filter_generation: u64,
+    // Global hotkey bindings
+    pub hotkeys: HotkeysConfig,
+    // Overlays hide their keys while set (toggled by hotkey or /ws command)
+    pub overlay_hidden: bool,
+    // Chord grouping options
+    pub display: DisplayConfig,
+    // Typed-text mode buffer
+    pub typed_text: TypedText,
+    // Recent presses, kept for `history_linger_ms` after release
+    pub history: KeyHistory,
+    // Released keys kept on screen until the deadline (minimum display time / release grace)
+    pub lingering: HashMap<u32, Instant>,
+    // Auto-repeats seen per held key code since its press
+    pub repeat_counts: HashMap<u32, u32>,
+    // KPS/APM/per-key counters since app start or the last `reset_stats`
+    pub stats: StatsEngine,
+    // Per-profile press totals kept across restarts (counters.json)
+    pub counters: LifetimeCounters,
+    // Counter-strafe analyzer options and results
+    pub strafe_config: StrafeConfig,
+    pub strafe: StrafeAnalyzer,
+    // Presses per minute of wall-clock time, for time-range heatmaps
+    pub press_log: PressLog,
+    // Chatter/rollover diagnostics, started on demand through the API
+    pub diagnostics: Diagnostics,
+    // JSONL session recording, started/stopped through the API
+    pub recorder: Recorder,
+    // Recording being played back; live input is ignored while set
+    pub replay: Option<Replay>,
+    // Press instant per tracked key code, used for hold durations
+    pub pressed_at: HashMap<u32, Instant>,
+    // Origin of the monotonic event clock
+    pub epoch: Instant,
     // Cache buster to invalidate OBS/browser cache on start/config change
     pub cache_buster: u64,
 }
@@ -394,39 +780,440 @@ impl AppState {
             app_config: AppConfig::default(),
             language: "ko".to_string(), // Default to Korean
             server_alive: false,
-            event_tx: None,
+            bus: EventBus::new(BUS_CAPACITY, BUS_HISTORY_LEN),
+            last_foreground: None,
+            profiles: ProfilesConfig::default(),
+            capture_paused: false,
+            privacy: PrivacyConfig::default(),
+            filter_generation: 0,
+            hotkeys: HotkeysConfig::default(),
+            overlay_hidden: false,
+            display: DisplayConfig::default(),
+            typed_text: TypedText::new(),
+            history: KeyHistory::new(),
+            lingering: HashMap::new(),
+            repeat_counts: HashMap::new(),
+            stats: StatsEngine::new(Instant::now()),
+            counters: LifetimeCounters::default(),
+            strafe_config: StrafeConfig::default(),
+            strafe: StrafeAnalyzer::new(&StrafeConfig::default()),
+            press_log: PressLog::default(),
+            diagnostics: Diagnostics::new(Instant::now()),
+            recorder: Recorder::default(),
+            replay: None,
+            pressed_at: HashMap::new(),
+            epoch: Instant::now(),
             cache_buster: 0,
         }
     }
 
-    pub fn set_event_tx(&mut self, tx: watch::Sender<Vec<String>>) {
-        self.event_tx = Some(tx);
+    // Milliseconds since app start on the monotonic clock
+    pub fn monotonic_ms(&self, at: Instant) -> f64 {
+        at.saturating_duration_since(self.epoch).as_secs_f64() * 1000.0
+    }
+
+    // Serialize once (with the seq it is about to get) and fan out to every client
+    pub fn broadcast(&mut self, message: ServerMessage) {
+        let json = Sequenced {
+            message: &message,
+            seq: self.bus.seq() + 1,
+        }
+        .to_json();
+        self.bus.publish(message.stream(), json);
+    }
+
+    // Fan out without a seq or a place in the history; for messages too large to retain
+    pub fn broadcast_transient(&mut self, message: ServerMessage) {
+        self.bus
+            .publish_transient(message.stream(), message.to_json());
+    }
+
+    // Every config section, for clients that may have missed transient `config_changed`
+    pub fn config_message(&self) -> ServerMessage {
+        ServerMessage::ConfigChanged {
+            overlay: Some(Box::new(self.app_config.overlay.clone())),
+            key_style: Some(Box::new(self.app_config.key_style.clone())),
+            key_images: Some(Box::new(self.app_config.key_images.clone())),
+        }
+    }
+
+    fn broadcast_keys(&mut self) {
+        let keys = self.get_keys();
+        self.broadcast(ServerMessage::Keys { keys });
+    }
+
+    // Input that may be persisted or recorded: not typed while paused, not replayed
+    fn is_live_input(&self) -> bool {
+        !self.capture_paused && self.replay.is_none()
+    }
+
+    // Code as published to clients; masked presses must not leak the physical key
+    fn public_code(&self, key_code: u32) -> u32 {
+        if self.capture_paused {
+            0
+        } else {
+            key_code
+        }
+    }
+
+    fn emit_release(&mut self, key_code: u32, label: String, now: Instant) {
+        let held_ms = self
+            .pressed_at
+            .get(&key_code)
+            .map(|at| now.saturating_duration_since(*at).as_secs_f64() * 1000.0)
+            .unwrap_or(0.0);
+        self.repeat_counts.remove(&key_code);
+        // Also covers keys cleared on pause/profile switch, so every recorded press gets its
+        // release; presses made while paused or replayed were never recorded
+        if self.is_live_input() {
+            let ts = self.monotonic_ms(now);
+            self.recorder
+                .record("release", ts, unix_ms(), key_code, &label);
+        }
+        if self.strafe_config.enabled {
+            let transitions = self.strafe.release(&self.strafe_config, &label, now);
+            self.emit_strafe(transitions, now);
+        }
+        self.broadcast(ServerMessage::Release {
+            code: self.public_code(key_code),
+            label,
+            ts: self.monotonic_ms(now),
+            held_ms,
+        });
+        if self.history.release(key_code, held_ms, now) {
+            // With no linger the entry goes right away
+            self.history.expire(self.history_linger(), now);
+            self.broadcast_history();
+        }
+    }
+
+    fn emit_strafe(&mut self, transitions: Vec<Transition>, now: Instant) {
+        let ts = self.monotonic_ms(now);
+        for transition in transitions {
+            self.broadcast(ServerMessage::Strafe {
+                from: transition.from,
+                to: transition.to,
+                delta_ms: transition.delta_ms,
+                ts,
+            });
+        }
+    }
+
+    fn history_linger(&self) -> Duration {
+        Duration::from_millis(self.display.history_linger_ms)
+    }
+
+    pub fn history_message(&self) -> ServerMessage {
+        ServerMessage::History {
+            entries: self.history.entries(),
+            linger_ms: self.display.history_linger_ms,
+        }
+    }
+
+    // Transient: every message is the full list, and a press would otherwise take a slot
+    // in the resume history even when nobody subscribed to `history`
+    fn broadcast_history(&mut self) {
+        let message = self.history_message();
+        self.broadcast_transient(message);
     }
 
+    // Part of a stats reset: the recent presses start over too
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+        self.broadcast_history();
+    }
+
+    // Drop released entries whose linger ran out (called on a timer by the server)
+    pub fn expire_history(&mut self, now: Instant) {
+        if self.history.expire(self.history_linger(), now) {
+            self.broadcast_history();
+        }
+    }
+
+    // Live input; ignored while a replay drives the state
     pub fn add_key(&mut self, key_code: u32, label: String) {
+        if self.replay.is_none() {
+            self.press_key(key_code, label);
+        }
+    }
+
+    pub fn remove_key(&mut self, key_code: u32) {
+        if self.replay.is_none() {
+            self.release_key(key_code);
+        }
+    }
+
+    pub fn key_repeat(&mut self, key_code: u32) {
+        if self.replay.is_none() {
+            self.repeat_key(key_code);
+        }
+    }
+
+    // One event of the running replay, handled like the live input it was recorded from
+    pub fn apply_replay(&mut self, event: ReplayEvent) {
+        match event.kind {
+            EventKind::Press => self.press_key(event.code, event.label),
+            EventKind::Release => self.release_key(event.code),
+            EventKind::Repeat => self.repeat_key(event.code),
+        }
+    }
+
+    fn press_key(&mut self, key_code: u32, label: String) {
+        // Pressed again while its release is still lingering: the chip never left the screen
+        let lingering = self.lingering.remove(&key_code).is_some();
         // Skip if this exact key code is already tracked
-        if self.key_labels.contains_key(&key_code) {
+        if !lingering && self.key_labels.contains_key(&key_code) {
             return;
         }
+        // While paused, either drop the press or show it under the mask character
+        let label = if lingering {
+            self.key_labels[&key_code].clone()
+        } else if self.capture_paused {
+            if !self.privacy.mask {
+                return;
+            }
+            self.privacy.mask_char.clone()
+        } else {
+            label
+        };
 
         // Track this key code -> label mapping
-        self.key_labels.insert(key_code, label.clone());
+        if !lingering {
+            self.key_labels.insert(key_code, label.clone());
+        }
+        let now = Instant::now();
+        self.pressed_at.insert(key_code, now);
+        // Nothing typed while paused (masked or not) or replayed is counted or persisted
+        if self.is_live_input() {
+            self.stats.record_press(&label, now);
+            self.counters
+                .record(self.profiles.active.as_deref(), &label);
+            self.press_log.record(&label, unix_ms());
+            let ts = self.monotonic_ms(now);
+            self.recorder
+                .record("press", ts, unix_ms(), key_code, &label);
+        }
+        if self.diagnostics.enabled() && self.is_live_input() {
+            let mut held: Vec<(&Instant, String)> = self
+                .pressed_at
+                .iter()
+                .filter_map(|(code, at)| Some((at, self.key_labels.get(code)?.clone())))
+                .collect();
+            held.sort();
+            let held = held.into_iter().map(|(_, label)| label).collect();
+            self.diagnostics.press(key_code, &label, held, now);
+        }
+        if self.strafe_config.enabled {
+            let transitions = self.strafe.press(&self.strafe_config, &label, now);
+            self.emit_strafe(transitions, now);
+        }
+        let ts = self.monotonic_ms(now);
+        self.broadcast(ServerMessage::Press {
+            code: self.public_code(key_code),
+            label: label.clone(),
+            ts,
+        });
+        self.history
+            .press(key_code, &label, ts, self.display.history_len);
+        self.broadcast_history();
 
-        // Increment reference count for this label
-        let count = self.label_counts.entry(label.clone()).or_insert(0);
-        *count += 1;
+        // Increment reference count for this label (a lingering key is still counted)
+        let first = !lingering && {
+            let count = self.label_counts.entry(label.clone()).or_insert(0);
+            *count += 1;
+            *count == 1
+        };
+
+        // Ahead of the `keys` update, so overlays can show the chord in its place right away
+        self.emit_chord(&label, now);
+        self.emit_text(&label, now);
 
         // Only add to display order if this is the first key with this label
-        if *count == 1 {
+        if first {
             self.label_order.push_back(label);
+            self.broadcast_keys();
         }
+    }
 
-        if let Some(tx) = &self.event_tx {
-            let _ = tx.send(self.get_keys());
+    // OS auto-repeat of a held key. Not a new press: no `press` event, nothing counted.
+    // Typed text still gets the character (the focused app receives it too).
+    fn repeat_key(&mut self, key_code: u32) {
+        if self.capture_paused || self.lingering.contains_key(&key_code) {
+            return;
+        }
+        let Some(label) = self.key_labels.get(&key_code).cloned() else {
+            return;
+        };
+        let count = self.repeat_counts.entry(key_code).or_insert(0);
+        *count += 1;
+        let count = *count;
+        let now = Instant::now();
+        let ts = self.monotonic_ms(now);
+        if self.replay.is_none() {
+            self.recorder
+                .record("repeat", ts, unix_ms(), key_code, &label);
+        }
+        if self.display.show_repeats {
+            self.broadcast(ServerMessage::Repeat {
+                code: key_code,
+                label: label.clone(),
+                count,
+                ts,
+            });
         }
+        self.emit_text(&label, now);
     }
 
-    pub fn remove_key(&mut self, key_code: u32) {
+    // Held modifiers in press order, then sorted by `modifier_order`:
+    // (normalized name, every raw label that maps to it).
+    // Keys already released but still on screen (`lingering`) don't count as held.
+    fn held_modifiers(&self) -> Vec<(String, Vec<String>)> {
+        let down: HashSet<&String> = self
+            .key_labels
+            .iter()
+            .filter(|(code, _)| !self.lingering.contains_key(code))
+            .map(|(_, label)| label)
+            .collect();
+        let mut modifiers: Vec<(String, Vec<String>)> = Vec::new();
+        for held in &self.label_order {
+            let name = normalize_label(held);
+            if !hotkeys::is_modifier(&name) || !down.contains(held) {
+                continue;
+            }
+            match modifiers.iter_mut().find(|(m, _)| *m == name) {
+                Some((_, raw)) => raw.push(held.clone()),
+                None => modifiers.push((name, vec![held.clone()])),
+            }
+        }
+        // Stable sort keeps press order for modifiers missing from `modifier_order`
+        modifiers.sort_by_key(|(m, _)| {
+            self.display
+                .modifier_order
+                .iter()
+                .position(|o| o == m)
+                .unwrap_or(usize::MAX)
+        });
+        modifiers
+    }
+
+    fn chord_label(&self, modifiers: &[(String, Vec<String>)], label: &str) -> String {
+        let mut parts: Vec<&str> = modifiers.iter().map(|(m, _)| m.as_str()).collect();
+        parts.push(label);
+        parts.join(&self.display.separator)
+    }
+
+    // In chord mode, a non-modifier pressed while modifiers are held becomes one shortcut entry
+    fn emit_chord(&mut self, label: &str, now: Instant) {
+        if !self.display.chord_mode
+            || self.capture_paused
+            || hotkeys::is_modifier(&normalize_label(label))
+        {
+            return;
+        }
+        let modifiers = self.held_modifiers();
+        if modifiers.is_empty()
+            || (self.display.skip_shift_only && modifiers.iter().all(|(m, _)| m == "SHIFT"))
+        {
+            return;
+        }
+        let chord = self.chord_label(&modifiers, label);
+        let mut keys: Vec<String> = modifiers.into_iter().flat_map(|(_, raw)| raw).collect();
+        keys.push(label.to_string());
+        self.broadcast(ServerMessage::Chord {
+            label: chord,
+            keys,
+            ts: self.monotonic_ms(now),
+        });
+    }
+
+    // In typed-text mode, fold the press into the text buffer and publish the result.
+    // Nothing is typed while paused, masked or not.
+    fn emit_text(&mut self, label: &str, now: Instant) {
+        if !self.display.text_mode
+            || self.capture_paused
+            || hotkeys::is_modifier(&normalize_label(label))
+        {
+            return;
+        }
+        let modifiers = self.held_modifiers();
+        let shift = modifiers.iter().any(|(m, _)| m == "SHIFT");
+        let shortcut = modifiers
+            .iter()
+            .any(|(m, _)| m != "SHIFT")
+            .then(|| self.chord_label(&modifiers, label));
+        let input = typed_text::classify(label, shift, shortcut);
+        if self.typed_text.press(label, input, &self.display, now) {
+            let message = self.text_message(now);
+            self.broadcast(message);
+        }
+    }
+
+    pub fn text_message(&self, now: Instant) -> ServerMessage {
+        ServerMessage::Text {
+            lines: self.typed_text.lines(),
+            plain: self.typed_text.plain(),
+            idle_ms: self.display.text_idle_ms,
+            ts: self.monotonic_ms(now),
+        }
+    }
+
+    // Display timing for a label: (minimum visible time, release grace),
+    // from its style group when that sets them, else the display defaults
+    fn key_timing(&self, label: &str) -> (Duration, Duration) {
+        let group = self
+            .app_config
+            .key_style
+            .raw_group_for(&self.app_config.overlay.overlay_mode, label);
+        let ms = |field: &str, default: u64| {
+            Duration::from_millis(group.and_then(|g| g[field].as_u64()).unwrap_or(default))
+        };
+        (
+            ms("minVisibleMs", self.display.min_visible_ms),
+            ms("releaseGraceMs", self.display.release_grace_ms),
+        )
+    }
+
+    fn release_key(&mut self, key_code: u32) {
+        // Already released, only still on screen
+        if self.lingering.contains_key(&key_code) {
+            return;
+        }
+        let Some(label) = self.key_labels.get(&key_code).cloned() else {
+            return;
+        };
+        // The release event goes out now; only the display removal may be deferred
+        let now = Instant::now();
+        self.emit_release(key_code, label.clone(), now);
+        if self.diagnostics.enabled() && self.replay.is_none() {
+            self.diagnostics.release(key_code, now);
+        }
+        let pressed_at = self.pressed_at.remove(&key_code).unwrap_or(now);
+
+        let (min_visible, grace) = self.key_timing(&label);
+        let deadline = (pressed_at + min_visible).max(now + grace);
+        if deadline > now {
+            self.lingering.insert(key_code, deadline);
+            return;
+        }
+        self.drop_key(key_code);
+    }
+
+    // Take released keys whose linger ran out off the screen (called on a timer by the server)
+    pub fn flush_lingering(&mut self, now: Instant) {
+        let due: Vec<u32> = self
+            .lingering
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(code, _)| *code)
+            .collect();
+        for code in due {
+            self.lingering.remove(&code);
+            self.drop_key(code);
+        }
+    }
+
+    // Remove a released key from the display
+    fn drop_key(&mut self, key_code: u32) {
         // Get and remove the label for this key code
         if let Some(label) = self.key_labels.remove(&key_code) {
             // Decrement reference count
@@ -437,35 +1224,40 @@ impl AppState {
                 if *count == 0 {
                     self.label_counts.remove(&label);
                     self.label_order.retain(|l| l != &label);
+                    self.broadcast_keys();
                 }
             }
-
-            if let Some(tx) = &self.event_tx {
-                let _ = tx.send(self.get_keys());
-            }
         }
     }
 
     pub fn clear_keys(&mut self) {
-        self.key_labels.clear();
+        // Release everything still held so event consumers don't keep stale holds
+        let now = Instant::now();
+        let held: Vec<(u32, String)> = self.key_labels.drain().collect();
+        let had_keys = !held.is_empty();
+        for (code, label) in held {
+            // Lingering keys already sent their release
+            if !self.lingering.contains_key(&code) {
+                self.emit_release(code, label, now);
+            }
+        }
+        self.lingering.clear();
+        self.pressed_at.clear();
         self.label_counts.clear();
         self.label_order.clear();
-        if let Some(tx) = &self.event_tx {
-            let _ = tx.send(self.get_keys());
+        if had_keys {
+            self.broadcast_keys();
         }
     }
 
-    // Check if a key code is currently tracked
+    // Check if a key code is currently held (not just lingering on screen)
     #[allow(dead_code)]
     pub fn is_key_pressed(&self, key_code: u32) -> bool {
-        self.key_labels.contains_key(&key_code)
+        self.key_labels.contains_key(&key_code) && !self.lingering.contains_key(&key_code)
     }
 
     pub fn bump_cache_buster(&mut self) {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default();
-        self.cache_buster = now.as_millis() as u64;
+        self.cache_buster = unix_ms();
     }
 
     pub fn get_keys(&self) -> Vec<String> {
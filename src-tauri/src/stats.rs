@@ -0,0 +1,111 @@
+use crate::rhythm::{RhythmAnalyzer, RhythmSnapshot};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+// Live typing statistics, fed by every real press that reaches `AppState::add_key`
+// (auto-repeats are not presses and never get here). Presses typed while capture is
+// paused and replayed presses are left out, like the lifetime counters.
+
+// Window KPS is measured over
+const KPS_WINDOW: Duration = Duration::from_secs(1);
+// Window APM is measured over (scaled up while the session is younger than this)
+const APM_WINDOW: Duration = Duration::from_secs(60);
+
+/// Stats as reported by `/api/stats` and the `stats` stream
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsSnapshot {
+    /// Presses since the last reset
+    pub presses: u64,
+    /// Presses in the last second
+    pub kps: f64,
+    /// Presses in the last minute, per minute
+    pub apm: f64,
+    /// Highest `kps` reached since the last reset
+    pub peak_kps: f64,
+    /// Press count per label since the last reset
+    pub per_key: BTreeMap<String, u64>,
+    /// Press timing of the current run (intervals, jitter, BPM, unstable rate)
+    pub rhythm: RhythmSnapshot,
+}
+
+pub struct StatsEngine {
+    // Press instants inside the APM window, oldest first (pruned on each press)
+    recent: VecDeque<Instant>,
+    per_key: BTreeMap<String, u64>,
+    presses: u64,
+    peak_kps: f64,
+    rhythm: RhythmAnalyzer,
+    since: Instant,
+}
+
+impl StatsEngine {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            recent: VecDeque::new(),
+            per_key: BTreeMap::new(),
+            presses: 0,
+            peak_kps: 0.0,
+            rhythm: RhythmAnalyzer::default(),
+            since: now,
+        }
+    }
+
+    pub fn reset(&mut self, now: Instant) {
+        *self = Self::new(now);
+    }
+
+    pub fn per_key(&self) -> &BTreeMap<String, u64> {
+        &self.per_key
+    }
+
+    pub fn record_press(&mut self, label: &str, now: Instant) {
+        self.presses += 1;
+        *self.per_key.entry(label.to_string()).or_insert(0) += 1;
+        self.recent.push_back(now);
+        self.prune(now);
+        self.peak_kps = self.peak_kps.max(self.kps(now));
+        self.rhythm.record_press(label, now);
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self
+            .recent
+            .front()
+            .is_some_and(|at| now.saturating_duration_since(*at) > APM_WINDOW)
+        {
+            self.recent.pop_front();
+        }
+    }
+
+    fn count_within(&self, window: Duration, now: Instant) -> usize {
+        self.recent
+            .iter()
+            .rev()
+            .take_while(|at| now.saturating_duration_since(**at) <= window)
+            .count()
+    }
+
+    fn kps(&self, now: Instant) -> f64 {
+        self.count_within(KPS_WINDOW, now) as f64 / KPS_WINDOW.as_secs_f64()
+    }
+
+    fn apm(&self, now: Instant) -> f64 {
+        // A fresh session hasn't filled the window yet; don't read 10 presses in 5s as 10 APM
+        let window = now
+            .saturating_duration_since(self.since)
+            .clamp(KPS_WINDOW, APM_WINDOW);
+        self.count_within(APM_WINDOW, now) as f64 * 60.0 / window.as_secs_f64()
+    }
+
+    pub fn snapshot(&self, now: Instant) -> StatsSnapshot {
+        StatsSnapshot {
+            presses: self.presses,
+            kps: self.kps(now),
+            apm: self.apm(now),
+            peak_kps: self.peak_kps,
+            per_key: self.per_key.clone(),
+            rhythm: self.rhythm.snapshot(),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use crate::state::StrafeConfig;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+// Counter-strafe analysis over the press/release stream (FPS movement coaching).
+// For each pair of opposing keys, the moment one key is let go is compared with the
+// moment the other is pressed: delta = press(new) - release(old).
+//   delta > 0: gap, neither key held in between
+//   delta < 0: overlap, both keys held at once (the new key went down first)
+
+// Largest `range_ms` the API accepts; counter-strafes are tens of ms
+pub const MAX_RANGE_MS: u64 = 1000;
+// Most histogram buckets per pair; the API rejects configs that would need more
+pub const MAX_BUCKETS: u64 = 400;
+
+// Buckets covering -range_ms..range_ms; clamped so a hand-edited strafe.json can't
+// make every pair allocate an enormous histogram
+pub fn bucket_count(config: &StrafeConfig) -> u64 {
+    config
+        .range_ms
+        .saturating_mul(2)
+        .div_ceil(config.bucket_ms.max(1))
+        .max(1)
+}
+
+/// One completed counter-strafe, as sent on the `strafe` stream
+#[derive(Debug, Clone)]
+pub struct Transition {
+    pub from: String,
+    pub to: String,
+    pub delta_ms: f64,
+}
+
+/// Per-pair results, as reported by `/api/strafe`
+#[derive(Debug, Clone, Serialize)]
+pub struct PairReport {
+    pub keys: [String; 2],
+    pub transitions: u64,
+    pub gaps: u64,
+    pub overlaps: u64,
+    /// Share of transitions where both keys were held at once
+    pub overlap_ratio: f64,
+    pub mean_ms: Option<f64>,
+    pub last_ms: Option<f64>,
+    /// Counts per `bucket_ms` bucket from `-range_ms` to `range_ms`
+    pub histogram: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StrafeReport {
+    pub enabled: bool,
+    pub bucket_ms: u64,
+    pub range_ms: u64,
+    pub pairs: Vec<PairReport>,
+}
+
+struct PairState {
+    keys: [String; 2],
+    held: [bool; 2],
+    released_at: [Option<Instant>; 2],
+    // Side pressed while the other was still held, and when
+    pending: Option<(usize, Instant)>,
+    transitions: u64,
+    overlaps: u64,
+    sum_ms: f64,
+    last_ms: Option<f64>,
+    histogram: Vec<u64>,
+}
+
+impl PairState {
+    fn side(&self, label: &str) -> Option<usize> {
+        self.keys
+            .iter()
+            .position(|key| key.eq_ignore_ascii_case(label))
+    }
+
+    fn record(&mut self, config: &StrafeConfig, to: usize, delta_ms: f64) -> Transition {
+        self.transitions += 1;
+        if delta_ms < 0.0 {
+            self.overlaps += 1;
+        }
+        self.sum_ms += delta_ms;
+        self.last_ms = Some(delta_ms);
+        let bucket = (delta_ms + config.range_ms as f64) / config.bucket_ms.max(1) as f64;
+        let last = self.histogram.len().saturating_sub(1);
+        if let Some(count) = self.histogram.get_mut((bucket.max(0.0) as usize).min(last)) {
+            *count += 1;
+        }
+        Transition {
+            from: self.keys[1 - to].clone(),
+            to: self.keys[to].clone(),
+            delta_ms,
+        }
+    }
+
+    fn report(&self) -> PairReport {
+        let ratio = |count: u64| match self.transitions {
+            0 => 0.0,
+            total => count as f64 / total as f64,
+        };
+        PairReport {
+            keys: self.keys.clone(),
+            transitions: self.transitions,
+            gaps: self.transitions - self.overlaps,
+            overlaps: self.overlaps,
+            overlap_ratio: ratio(self.overlaps),
+            mean_ms: (self.transitions > 0).then(|| self.sum_ms / self.transitions as f64),
+            last_ms: self.last_ms,
+            histogram: self.histogram.clone(),
+        }
+    }
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+pub struct StrafeAnalyzer {
+    pairs: Vec<PairState>,
+}
+
+impl StrafeAnalyzer {
+    // Built from the config; rebuilt (dropping results) when pairs or buckets change
+    pub fn new(config: &StrafeConfig) -> Self {
+        let buckets = bucket_count(config).min(MAX_BUCKETS) as usize;
+        let pairs = config
+            .pairs
+            .iter()
+            .map(|keys| PairState {
+                keys: keys.clone(),
+                held: [false; 2],
+                released_at: [None; 2],
+                pending: None,
+                transitions: 0,
+                overlaps: 0,
+                sum_ms: 0.0,
+                last_ms: None,
+                histogram: vec![0; buckets],
+            })
+            .collect();
+        Self { pairs }
+    }
+
+    pub fn press(&mut self, config: &StrafeConfig, label: &str, now: Instant) -> Vec<Transition> {
+        let mut transitions = Vec::new();
+        for pair in &mut self.pairs {
+            let Some(side) = pair.side(label) else {
+                continue;
+            };
+            let other = 1 - side;
+            pair.held[side] = true;
+            if pair.held[other] {
+                // Overlap: settled when the other key comes up while this one is still down
+                pair.pending = Some((side, now));
+            } else if let Some(released) = pair.released_at[other] {
+                let gap = now.saturating_duration_since(released);
+                if gap <= Duration::from_millis(config.max_gap_ms) {
+                    transitions.push(pair.record(config, side, ms(gap)));
+                }
+                pair.released_at[other] = None;
+            }
+        }
+        transitions
+    }
+
+    pub fn release(&mut self, config: &StrafeConfig, label: &str, now: Instant) -> Vec<Transition> {
+        let mut transitions = Vec::new();
+        for pair in &mut self.pairs {
+            let Some(side) = pair.side(label) else {
+                continue;
+            };
+            let other = 1 - side;
+            pair.held[side] = false;
+            match pair.pending.take() {
+                Some((pressed, at)) if pressed == other && pair.held[other] => {
+                    let overlap = now.saturating_duration_since(at);
+                    transitions.push(pair.record(config, other, -ms(overlap)));
+                }
+                // The key pressed during the overlap let go first: no direction change
+                _ => pair.released_at[side] = Some(now),
+            }
+        }
+        transitions
+    }
+
+    pub fn report(&self, config: &StrafeConfig) -> StrafeReport {
+        StrafeReport {
+            enabled: config.enabled,
+            bucket_ms: config.bucket_ms,
+            range_ms: config.range_ms,
+            pairs: self.pairs.iter().map(PairState::report).collect(),
+        }
+    }
+}
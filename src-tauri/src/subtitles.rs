@@ -0,0 +1,296 @@
+use crate::cli::{write_output, CliArgs};
+use crate::hotkeys::{is_modifier, normalize_label};
+use crate::replay::{EventKind, ReplayEvent};
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+// Session recordings (`recorder.rs`) as SRT/ASS subtitles, for burning key presses into
+// tutorial videos. One cue per change of the held keys, timed from the first event.
+// With chord merging, modifiers are written like chord mode does (CTRL + SHIFT + S) and
+// a modifier held on its own right before or after a shortcut joins that shortcut's cue.
+
+pub const DEFAULT_MIN_DURATION_MS: u64 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Srt,
+    Ass,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "srt" => Some(Format::Srt),
+            "ass" => Some(Format::Ass),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Srt => "srt",
+            Format::Ass => "ass",
+        }
+    }
+}
+
+pub struct SubtitleOptions {
+    pub format: Format,
+    pub merge_chords: bool,
+    /// Short cues are stretched to this, but never past the start of the next cue
+    pub min_duration_ms: u64,
+    /// Added to every cue (negative moves them earlier), to line up with the video
+    pub offset_ms: i64,
+    /// Chord separator and modifier order, as in `DisplayConfig`
+    pub separator: String,
+    pub modifier_order: Vec<String>,
+}
+
+struct Cue {
+    start: f64,
+    end: f64,
+    text: String,
+    // Normalized modifiers held, and whether a non-modifier key was held too
+    modifiers: Vec<String>,
+    chord: bool,
+}
+
+// Held keys (raw labels, press order) for every span in which they didn't change
+fn held_spans(events: &[ReplayEvent]) -> Vec<(f64, f64, Vec<String>)> {
+    let mut spans = Vec::new();
+    let mut held: Vec<(u32, String)> = Vec::new();
+    let mut since = 0.0;
+    for event in events {
+        let changes = match event.kind {
+            EventKind::Press => !held.iter().any(|(code, _)| *code == event.code),
+            EventKind::Release => held.iter().any(|(code, _)| *code == event.code),
+            EventKind::Repeat => false,
+        };
+        if !changes {
+            continue;
+        }
+        // Keys that change within the same millisecond never show up on their own
+        if !held.is_empty() && event.at_ms > since {
+            let labels = held.iter().map(|(_, label)| label.clone()).collect();
+            spans.push((since, event.at_ms, labels));
+        }
+        since = event.at_ms;
+        match event.kind {
+            EventKind::Press => held.push((event.code, event.label.clone())),
+            _ => held.retain(|(code, _)| *code != event.code),
+        }
+    }
+    // Recording stopped with keys still down: a cue of the minimum duration
+    if !held.is_empty() {
+        let labels = held.into_iter().map(|(_, label)| label).collect();
+        spans.push((since, since, labels));
+    }
+    spans
+}
+
+fn cue(start: f64, end: f64, keys: Vec<String>, options: &SubtitleOptions) -> Cue {
+    let raw = keys.join(&options.separator);
+    let mut modifiers: Vec<String> = Vec::new();
+    let mut others: Vec<String> = Vec::new();
+    for key in keys {
+        let name = normalize_label(&key);
+        if !is_modifier(&name) {
+            others.push(key);
+        } else if !modifiers.contains(&name) {
+            modifiers.push(name);
+        }
+    }
+    // Stable sort keeps press order for modifiers missing from `modifier_order`
+    modifiers.sort_by_key(|m| {
+        options
+            .modifier_order
+            .iter()
+            .position(|o| o.eq_ignore_ascii_case(m))
+            .unwrap_or(usize::MAX)
+    });
+    let text = if options.merge_chords {
+        modifiers
+            .iter()
+            .chain(others.iter())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(&options.separator)
+    } else {
+        raw
+    };
+    Cue {
+        start,
+        end,
+        text,
+        chord: !others.is_empty(),
+        modifiers,
+    }
+}
+
+// A modifier-only cue touching a shortcut cue that holds the same modifiers joins it
+fn absorbs(chord: &Cue, modifiers: &Cue) -> bool {
+    chord.chord
+        && modifiers
+            .modifiers
+            .iter()
+            .all(|m| chord.modifiers.contains(m))
+}
+
+fn merge_chords(cues: Vec<Cue>) -> Vec<Cue> {
+    // Lead-in: CTRL, CTRL + SHIFT, then CTRL + SHIFT + S (walked backwards so it chains)
+    let mut lead: Vec<Cue> = Vec::new();
+    for cue in cues.into_iter().rev() {
+        match lead.last_mut() {
+            Some(next) if !cue.chord && next.start == cue.end && absorbs(next, &cue) => {
+                next.start = cue.start;
+            }
+            _ => lead.push(cue),
+        }
+    }
+    // Trail: CTRL + S, then S let go before CTRL
+    let mut merged: Vec<Cue> = Vec::new();
+    for cue in lead.into_iter().rev() {
+        match merged.last_mut() {
+            Some(last) if !cue.chord && last.end == cue.start && absorbs(last, &cue) => {
+                last.end = cue.end;
+            }
+            // Same text again (e.g. LSHIFT traded for RSHIFT)
+            Some(last) if last.end == cue.start && last.text == cue.text => {
+                last.end = cue.end;
+            }
+            _ => merged.push(cue),
+        }
+    }
+    merged
+}
+
+fn build_cues(events: &[ReplayEvent], options: &SubtitleOptions) -> Vec<Cue> {
+    let mut cues: Vec<Cue> = held_spans(events)
+        .into_iter()
+        .map(|(start, end, keys)| cue(start, end, keys, options))
+        .collect();
+    if options.merge_chords {
+        cues = merge_chords(cues);
+    }
+    let min = options.min_duration_ms as f64;
+    let mut next_start: Option<f64> = None;
+    for cue in cues.iter_mut().rev() {
+        let stretched = cue.end.max(cue.start + min);
+        cue.end = next_start.map_or(stretched, |next| stretched.min(next));
+        next_start = Some(cue.start);
+    }
+    let offset = options.offset_ms as f64;
+    cues.into_iter()
+        .filter_map(|mut cue| {
+            cue.start = (cue.start + offset).max(0.0);
+            cue.end += offset;
+            (cue.end > cue.start).then_some(cue)
+        })
+        .collect()
+}
+
+// 01:02:03,456 (SRT) / 1:02:03.45 (ASS)
+fn timestamp(ms: f64, format: Format) -> String {
+    let ms = ms.round() as u64;
+    let (h, m, s) = (ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60);
+    match format {
+        Format::Srt => format!("{:02}:{:02}:{:02},{:03}", h, m, s, ms % 1000),
+        Format::Ass => format!("{}:{:02}:{:02}.{:02}", h, m, s, ms % 1000 / 10),
+    }
+}
+
+// Braces open override blocks and a backslash starts an escape like \N
+fn escape_ass(text: &str) -> String {
+    text.replace('\\', "\\\u{200B}")
+        .replace('{', "\\{")
+        .replace('}', "\\}")
+}
+
+pub fn render(events: &[ReplayEvent], options: &SubtitleOptions) -> String {
+    let cues = build_cues(events, options);
+    let mut out = String::new();
+    match options.format {
+        Format::Srt => {
+            for (index, cue) in cues.iter().enumerate() {
+                let _ = writeln!(out, "{}", index + 1);
+                let _ = writeln!(
+                    out,
+                    "{} --> {}",
+                    timestamp(cue.start, Format::Srt),
+                    timestamp(cue.end, Format::Srt)
+                );
+                let _ = writeln!(out, "{}", cue.text);
+                let _ = writeln!(out);
+            }
+        }
+        Format::Ass => {
+            out.push_str(
+                "[Script Info]\n\
+                 Title: KeyViewer key presses\n\
+                 ScriptType: v4.00+\n\
+                 PlayResX: 1920\n\
+                 PlayResY: 1080\n\
+                 WrapStyle: 2\n\
+                 \n\
+                 [V4+ Styles]\n\
+                 Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n\
+                 Style: Keys,Arial,56,&H00FFFFFF,&H000000FF,&H00000000,&H99000000,-1,0,0,0,100,100,0,0,3,12,0,2,40,40,60,1\n\
+                 \n\
+                 [Events]\n\
+                 Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+            );
+            for cue in &cues {
+                let _ = writeln!(
+                    out,
+                    "Dialogue: 0,{},{},Keys,,0,0,0,,{}",
+                    timestamp(cue.start, Format::Ass),
+                    timestamp(cue.end, Format::Ass),
+                    escape_ass(&cue.text)
+                );
+            }
+        }
+    }
+    out
+}
+
+const CLI_USAGE: &str = "Usage: KBQV export-subtitles <recording id | file.jsonl> [--format srt|ass] [--output PATH] [--no-merge-chords] [--min-duration-ms N] [--offset-ms N]";
+
+// `KBQV export-subtitles ...`: runs instead of the app. A recording id exports every part
+// of that session from the recordings directory; a file path exports just that file.
+// Writes next to the input (`<id>.srt`) unless `--output` is given.
+pub fn run_cli(args: &[String]) -> Result<PathBuf, String> {
+    let display = crate::settings::load_display_config();
+    let mut options = SubtitleOptions {
+        format: Format::Srt,
+        merge_chords: true,
+        min_duration_ms: DEFAULT_MIN_DURATION_MS,
+        offset_ms: 0,
+        separator: display.separator,
+        modifier_order: display.modifier_order,
+    };
+    let cli = CliArgs::parse(
+        args,
+        CLI_USAGE,
+        &["--format", "--min-duration-ms", "--offset-ms"],
+        &["--no-merge-chords"],
+    )?;
+    let number = |name: &str| format!("{} must be a number\n{}", name, CLI_USAGE);
+    for (name, value) in &cli.options {
+        match name.as_str() {
+            "--format" => {
+                options.format = Format::parse(value)
+                    .ok_or_else(|| format!("Unknown format: {}\n{}", value, CLI_USAGE))?;
+            }
+            "--no-merge-chords" => options.merge_chords = false,
+            "--min-duration-ms" => {
+                options.min_duration_ms = value.parse().map_err(|_| number(name))?;
+            }
+            "--offset-ms" => options.offset_ms = value.parse().map_err(|_| number(name))?,
+            _ => {}
+        }
+    }
+    let (paths, output) = cli.files(&format!(".{}", options.format.extension()))?;
+    let events = crate::replay::load_paths(&paths)?;
+    write_output(&output, render(&events, &options))?;
+    Ok(output)
+}
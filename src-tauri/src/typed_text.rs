@@ -0,0 +1,418 @@
+use crate::state::DisplayConfig;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Typed-text mode: key presses are folded into lines of text the way a screencast
+// shows typing. The buffer lives on the server so every overlay/SSE client renders
+// the same lines. Characters assume a US layout (Shift + 1 is "!").
+
+/// One piece of a typed-text line
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Segment {
+    Text {
+        text: String,
+    },
+    /// Shortcut, non-printing key or collapsed run, shown as a badge (`A ×5` when `count` > 1)
+    Key {
+        label: String,
+        count: u32,
+        // Character the run was collapsed from, so Backspace can shorten it
+        #[serde(skip)]
+        typed: Option<char>,
+    },
+}
+
+impl Segment {
+    fn width(&self) -> usize {
+        match self {
+            Segment::Text { text } => text.chars().count(),
+            Segment::Key { label, .. } => label.chars().count() + 2,
+        }
+    }
+
+    fn render(&self) -> String {
+        match self {
+            Segment::Text { text } => text.clone(),
+            Segment::Key { label, count, .. } if *count > 1 => format!("[{} ×{}]", label, count),
+            Segment::Key { label, .. } => format!("[{}]", label),
+        }
+    }
+}
+
+/// What a single press does to the text
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    Char(char),
+    Backspace,
+    Enter,
+    Badge(String),
+}
+
+const SHIFTED_US: [(char, char); 21] = [
+    ('1', '!'),
+    ('2', '@'),
+    ('3', '#'),
+    ('4', '$'),
+    ('5', '%'),
+    ('6', '^'),
+    ('7', '&'),
+    ('8', '*'),
+    ('9', '('),
+    ('0', ')'),
+    ('`', '~'),
+    ('-', '_'),
+    ('=', '+'),
+    ('[', '{'),
+    (']', '}'),
+    ('\\', '|'),
+    (';', ':'),
+    ('\'', '"'),
+    (',', '<'),
+    ('.', '>'),
+    ('/', '?'),
+];
+
+// Map a key label (as produced by keyboard.rs) to its effect on the text.
+// `shortcut` is the chord label when non-Shift modifiers are held.
+pub fn classify(label: &str, shift: bool, shortcut: Option<String>) -> Input {
+    if let Some(shortcut) = shortcut {
+        return Input::Badge(shortcut);
+    }
+    match label {
+        "SPACE" => return Input::Char(' '),
+        "ENTER" => return Input::Enter,
+        "BKSP" => return Input::Backspace,
+        _ => {}
+    }
+    let mut chars = label.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => Input::Char(if shift {
+            c.to_ascii_uppercase()
+        } else {
+            c.to_ascii_lowercase()
+        }),
+        (Some(c), None) if !c.is_whitespace() => {
+            let shifted = SHIFTED_US
+                .iter()
+                .find(|(plain, _)| *plain == c)
+                .map(|(_, shifted)| *shifted);
+            match shifted {
+                Some(shifted) if shift => Input::Char(shifted),
+                _ => Input::Char(c),
+            }
+        }
+        _ => Input::Badge(label.to_string()),
+    }
+}
+
+/// Rolling typed-text buffer; the last line is the one being typed
+pub struct TypedText {
+    lines: VecDeque<Vec<Segment>>,
+    // Key label and character of the current run of identical presses
+    run: Option<(String, Input)>,
+    run_len: u32,
+    last_press: Option<Instant>,
+}
+
+impl Default for TypedText {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypedText {
+    pub fn new() -> Self {
+        Self {
+            lines: VecDeque::from([Vec::new()]),
+            run: None,
+            run_len: 0,
+            last_press: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    pub fn lines(&self) -> Vec<Vec<Segment>> {
+        self.lines.iter().cloned().collect()
+    }
+
+    // Lines flattened to strings, badges in brackets (`hello [CTRL+S] [A ×5]`)
+    pub fn plain(&self) -> Vec<String> {
+        self.lines
+            .iter()
+            .map(|line| line.iter().map(Segment::render).collect())
+            .collect()
+    }
+
+    // Apply one press; returns false when nothing visible changed
+    pub fn press(
+        &mut self,
+        label: &str,
+        input: Input,
+        config: &DisplayConfig,
+        now: Instant,
+    ) -> bool {
+        // After an idle gap the old text is stale; start over
+        let idle = Duration::from_millis(config.text_idle_ms);
+        if config.text_idle_ms > 0
+            && self
+                .last_press
+                .is_some_and(|last| now.saturating_duration_since(last) > idle)
+        {
+            self.clear();
+        }
+        self.last_press = Some(now);
+
+        let repeat = self
+            .run
+            .as_ref()
+            .is_some_and(|(run_label, run_input)| run_label == label && *run_input == input);
+        self.run_len = if repeat { self.run_len + 1 } else { 1 };
+        self.run = Some((label.to_string(), input.clone()));
+
+        let changed = match input {
+            Input::Char(c) => {
+                self.push_char(label, c, config.text_collapse_after);
+                true
+            }
+            Input::Backspace => self.backspace(),
+            Input::Enter => self.enter(config.text_max_lines),
+            Input::Badge(badge) => {
+                self.push_badge(badge);
+                true
+            }
+        };
+        self.trim_line(config.text_max_chars);
+        changed
+    }
+
+    fn current(&mut self) -> &mut Vec<Segment> {
+        if self.lines.is_empty() {
+            self.lines.push_back(Vec::new());
+        }
+        self.lines.back_mut().expect("at least one line")
+    }
+
+    fn push_char(&mut self, label: &str, c: char, collapse_after: u32) {
+        let run_len = self.run_len;
+        let line = self.current();
+        // Run already collapsed into a badge: just count up
+        if let Some(Segment::Key { count, typed, .. }) = line.last_mut() {
+            if *typed == Some(c) && run_len > 1 {
+                *count += 1;
+                return;
+            }
+        }
+        if collapse_after > 0 && run_len > collapse_after {
+            // Take the run's characters back out of the text and show it as `A ×n`.
+            // `trim_line` may already have dropped some of them, so only the ones still
+            // at the end of the text are taken.
+            if let Some(Segment::Text { text }) = line.last_mut() {
+                let typed = text.chars().rev().take_while(|typed| *typed == c).count();
+                for _ in 0..typed.min(run_len as usize - 1) {
+                    text.pop();
+                }
+                if text.is_empty() {
+                    line.pop();
+                }
+            }
+            let label = if c == ' ' { "SPACE" } else { label };
+            line.push(Segment::Key {
+                label: label.to_string(),
+                count: run_len,
+                typed: Some(c),
+            });
+            return;
+        }
+        match line.last_mut() {
+            Some(Segment::Text { text }) => text.push(c),
+            _ => line.push(Segment::Text {
+                text: c.to_string(),
+            }),
+        }
+    }
+
+    fn push_badge(&mut self, badge: String) {
+        let line = self.current();
+        if let Some(Segment::Key {
+            label,
+            count,
+            typed: None,
+        }) = line.last_mut()
+        {
+            if *label == badge {
+                *count += 1;
+                return;
+            }
+        }
+        line.push(Segment::Key {
+            label: badge,
+            count: 1,
+            typed: None,
+        });
+    }
+
+    fn backspace(&mut self) -> bool {
+        // Deleting interrupts whatever run was being typed
+        self.run = None;
+        if self.lines.back().is_some_and(|line| line.is_empty()) {
+            // Empty line: undo the line break
+            if self.lines.len() > 1 {
+                self.lines.pop_back();
+                return true;
+            }
+            return false;
+        }
+        let line = self.current();
+        match line.last_mut() {
+            Some(Segment::Text { text }) => {
+                text.pop();
+                if text.is_empty() {
+                    line.pop();
+                }
+            }
+            Some(Segment::Key {
+                count,
+                typed: Some(_),
+                ..
+            }) if *count > 1 => *count -= 1,
+            Some(Segment::Key { typed: Some(_), .. }) => {
+                line.pop();
+            }
+            // A shortcut badge can't be deleted; show the Backspace itself
+            _ => self.push_badge("BKSP".to_string()),
+        }
+        true
+    }
+
+    fn enter(&mut self, max_lines: usize) -> bool {
+        self.run = None;
+        // Repeated Enter doesn't stack blank lines
+        if self.lines.back().is_some_and(|line| line.is_empty()) {
+            return false;
+        }
+        self.lines.push_back(Vec::new());
+        while self.lines.len() > max_lines.max(1) {
+            self.lines.pop_front();
+        }
+        true
+    }
+
+    // Keep the current line within `max_chars` by dropping its oldest text
+    fn trim_line(&mut self, max_chars: usize) {
+        if max_chars == 0 {
+            return;
+        }
+        let line = self.current();
+        let mut width: usize = line.iter().map(Segment::width).sum();
+        while width > max_chars && line.len() > 1 {
+            let overflow = width - max_chars;
+            match &mut line[0] {
+                Segment::Text { text } if text.chars().count() > overflow => {
+                    *text = text.chars().skip(overflow).collect();
+                    width -= overflow;
+                }
+                first => {
+                    width -= first.width();
+                    line.remove(0);
+                }
+            }
+        }
+        if let [Segment::Text { text }] = line.as_mut_slice() {
+            let len = text.chars().count();
+            if len > max_chars {
+                *text = text.chars().skip(len - max_chars).collect();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> DisplayConfig {
+        DisplayConfig {
+            text_mode: true,
+            ..DisplayConfig::default()
+        }
+    }
+
+    fn type_labels(text: &mut TypedText, labels: &[&str], config: &DisplayConfig) {
+        let now = Instant::now();
+        for label in labels {
+            text.press(label, classify(label, false, None), config, now);
+        }
+    }
+
+    #[test]
+    fn run_collapses_into_a_badge() {
+        let config = config();
+        let mut text = TypedText::new();
+        type_labels(&mut text, &["H", "I", "A", "A", "A"], &config);
+        assert_eq!(text.plain(), vec!["hiaaa"]);
+        type_labels(&mut text, &["A", "A"], &config);
+        assert_eq!(text.plain(), vec!["hi[A ×5]"]);
+        type_labels(&mut text, &["B"], &config);
+        assert_eq!(text.plain(), vec!["hi[A ×5]b"]);
+    }
+
+    #[test]
+    fn backspace_shortens_a_collapsed_run() {
+        let config = config();
+        let mut text = TypedText::new();
+        type_labels(&mut text, &["X", "A", "A", "A", "A"], &config);
+        assert_eq!(text.plain(), vec!["x[A ×4]"]);
+        type_labels(&mut text, &["BKSP"], &config);
+        assert_eq!(text.plain(), vec!["x[A ×3]"]);
+        type_labels(&mut text, &["BKSP", "BKSP"], &config);
+        assert_eq!(text.plain(), vec!["x[A]"]);
+        type_labels(&mut text, &["BKSP"], &config);
+        assert_eq!(text.plain(), vec!["x"]);
+        type_labels(&mut text, &["BKSP"], &config);
+        assert_eq!(text.plain(), vec![""]);
+    }
+
+    #[test]
+    fn enter_starts_a_line_without_stacking_blanks() {
+        let config = config();
+        let mut text = TypedText::new();
+        type_labels(&mut text, &["A", "ENTER", "ENTER", "B"], &config);
+        assert_eq!(text.plain(), vec!["a", "b"]);
+        // Only `text_max_lines` (2) lines are kept
+        type_labels(&mut text, &["ENTER", "C"], &config);
+        assert_eq!(text.plain(), vec!["b", "c"]);
+        // Backspace on an empty line undoes the break (the line it pushed out stays gone)
+        type_labels(&mut text, &["ENTER"], &config);
+        assert_eq!(text.plain(), vec!["c", ""]);
+        type_labels(&mut text, &["BKSP"], &config);
+        assert_eq!(text.plain(), vec!["c"]);
+    }
+
+    #[test]
+    fn collapse_after_trim_keeps_earlier_text() {
+        let config = DisplayConfig {
+            text_max_chars: 4,
+            text_collapse_after: 5,
+            ..config()
+        };
+        let mut text = TypedText::new();
+        type_labels(&mut text, &["X", "Y"], &config);
+        type_labels(&mut text, &["A"; 5], &config);
+        // The line only has room for four of the five characters
+        assert_eq!(text.plain(), vec!["aaaa"]);
+        type_labels(&mut text, &["A"], &config);
+        assert_eq!(text.plain(), vec!["[A ×6]"]);
+
+        let mut text = TypedText::new();
+        type_labels(&mut text, &["X", "A", "A", "A", "A"], &config);
+        assert_eq!(text.plain(), vec!["aaaa"]);
+        type_labels(&mut text, &["CTRL+S"], &config);
+        type_labels(&mut text, &["B", "A"], &config);
+        assert_eq!(text.plain(), vec!["ba"]);
+    }
+}